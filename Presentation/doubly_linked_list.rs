@@ -0,0 +1,235 @@
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+// Cada nodo puede tener varios dueños, por eso no alcanza con Box:
+// - Rc<T> cuenta cuantos punteros "fuertes" apuntan al nodo y lo libera
+//   cuando el contador llega a cero
+// - RefCell<T> permite modificar el nodo aunque este compartido, revisando
+//   las reglas de prestamo en tiempo de ejecucion
+// - Weak<T> es un puntero "debil": no cuenta como dueño, asi que el enlace
+//   hacia atras no forma un ciclo que nunca se libere
+//
+// Los enlaces hacia adelante (next) son fuertes y los enlaces hacia atras
+// (prev) son debiles. La lista tambien guarda un puntero fuerte a la cola.
+type Link = Option<Rc<RefCell<DNode>>>;
+
+struct DNode {
+    data: i32,
+    next: Link,
+    prev: Option<Weak<RefCell<DNode>>>,
+}
+
+struct DList {
+    head: Link,
+    tail: Link,
+}
+
+impl DNode {
+    // constructor
+    fn new(data: i32) -> Rc<RefCell<DNode>> {
+        Rc::new(RefCell::new(DNode {
+            data,
+            next: None,
+            prev: None,
+        }))
+    }
+}
+
+impl DList {
+    // lista vacia
+    fn new() -> DList {
+        DList {
+            head: None,
+            tail: None,
+        }
+    }
+
+    // agregar un elemento al inicio
+    fn push_front(&mut self, data: i32) {
+        let new_node = DNode::new(data);
+        match self.head.take() {
+            Some(old_head) => {
+                // la antigua cabeza apunta hacia atras al nuevo nodo
+                old_head.borrow_mut().prev = Some(Rc::downgrade(&new_node));
+                new_node.borrow_mut().next = Some(old_head);
+            }
+            // si la lista estaba vacia el nuevo nodo tambien es la cola
+            None => self.tail = Some(Rc::clone(&new_node)),
+        }
+        self.head = Some(new_node);
+    }
+
+    // agregar un elemento al final
+    fn push_back(&mut self, data: i32) {
+        let new_node = DNode::new(data);
+        match self.tail.take() {
+            Some(old_tail) => {
+                new_node.borrow_mut().prev = Some(Rc::downgrade(&old_tail));
+                old_tail.borrow_mut().next = Some(Rc::clone(&new_node));
+            }
+            None => self.head = Some(Rc::clone(&new_node)),
+        }
+        self.tail = Some(new_node);
+    }
+
+    // quitar el primer elemento
+    fn pop_front(&mut self) -> Option<i32> {
+        let old_head = self.head.take()?;
+        match old_head.borrow_mut().next.take() {
+            Some(new_head) => {
+                new_head.borrow_mut().prev = None;
+                self.head = Some(new_head);
+            }
+            // era el unico elemento
+            None => self.tail = None,
+        }
+        let data = old_head.borrow().data;
+        Some(data)
+    }
+
+    // quitar el ultimo elemento
+    fn pop_back(&mut self) -> Option<i32> {
+        let old_tail = self.tail.take()?;
+        // upgrade() convierte el puntero debil en uno fuerte si el nodo existe
+        let prev = old_tail.borrow_mut().prev.take();
+        match prev.and_then(|prev| prev.upgrade()) {
+            Some(new_tail) => {
+                new_tail.borrow_mut().next = None;
+                self.tail = Some(new_tail);
+            }
+            None => self.head = None,
+        }
+        let data = old_tail.borrow().data;
+        Some(data)
+    }
+
+    // recorrer la lista desde la cabeza
+    fn iter(&self) -> Iter {
+        Iter {
+            next: self.head.clone(),
+        }
+    }
+
+    // recorrer la lista desde la cola
+    fn iter_rev(&self) -> IterRev {
+        IterRev {
+            next: self.tail.clone(),
+        }
+    }
+}
+
+// Sin esto, soltar la cabeza soltaria el siguiente nodo, y ese el siguiente,
+// de forma recursiva; con una lista muy larga se desborda la pila
+impl Drop for DList {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+struct Iter {
+    next: Link,
+}
+
+impl Iterator for Iter {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<i32> {
+        let node = self.next.take()?;
+        self.next = node.borrow().next.clone();
+        let data = node.borrow().data;
+        Some(data)
+    }
+}
+
+struct IterRev {
+    next: Link,
+}
+
+impl Iterator for IterRev {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<i32> {
+        let node = self.next.take()?;
+        self.next = node.borrow().prev.as_ref().and_then(|prev| prev.upgrade());
+        let data = node.borrow().data;
+        Some(data)
+    }
+}
+
+fn main() {
+    let mut list = DList::new();
+    for data in 1..=5 {
+        list.push_back(data);
+    }
+    list.push_front(0);
+
+    // imprimir la lista en ambos sentidos
+    for data in list.iter() {
+        print!("{} ", data);
+    }
+    println!();
+    for data in list.iter_rev() {
+        print!("{} ", data);
+    }
+    println!();
+
+    println!("Primero: {:?}", list.pop_front());
+    println!("Ultimo: {:?}", list.pop_back());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(data: &[i32]) -> DList {
+        let mut list = DList::new();
+        for &d in data {
+            list.push_back(d);
+        }
+        list
+    }
+
+    #[test]
+    fn traverses_both_ways() {
+        let list = list(&[1, 2, 3, 4]);
+        assert_eq!(vec![1, 2, 3, 4], list.iter().collect::<Vec<_>>());
+        assert_eq!(vec![4, 3, 2, 1], list.iter_rev().collect::<Vec<_>>());
+
+        let empty = DList::new();
+        assert_eq!(None, empty.iter().next());
+        assert_eq!(None, empty.iter_rev().next());
+    }
+
+    #[test]
+    fn push_and_pop_at_both_ends() {
+        let mut list = DList::new();
+        list.push_back(2);
+        list.push_front(1);
+        list.push_back(3);
+        list.push_front(0);
+        assert_eq!(vec![0, 1, 2, 3], list.iter().collect::<Vec<_>>());
+
+        assert_eq!(Some(0), list.pop_front());
+        assert_eq!(Some(3), list.pop_back());
+        assert_eq!(vec![2, 1], list.iter_rev().collect::<Vec<_>>());
+
+        assert_eq!(Some(2), list.pop_back());
+        assert_eq!(Some(1), list.pop_front());
+        assert_eq!(None, list.pop_front());
+        assert_eq!(None, list.pop_back());
+        assert!(list.head.is_none() && list.tail.is_none());
+    }
+
+    #[test]
+    fn popped_nodes_are_freed() {
+        let mut list = list(&[1, 2, 3]);
+        let middle = Rc::downgrade(list.head.as_ref().unwrap().borrow().next.as_ref().unwrap());
+
+        list.pop_front();
+        list.pop_back();
+        assert!(middle.upgrade().is_some());
+        list.pop_back();
+        // no quedan punteros fuertes, asi que el nodo se libero
+        assert!(middle.upgrade().is_none());
+    }
+}
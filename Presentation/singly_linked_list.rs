@@ -1,3 +1,6 @@
+use std::fmt;
+
+#[derive(Debug)]
 struct Node {
     data: i32,
     // Tenemos dos opciones, o utilizamos Some() o None()
@@ -16,29 +19,236 @@ impl Node {
     }
     
     // dos argumentos: referencia al nodo y un vector i32
+    // los datos del vector se enganchan despues del nodo, en el mismo orden
     fn create(node: &mut Node, data: Vec<i32>) {
-        // nuevo nodo
-        let new_node = Node::new(data[0]);
-        // asignar el siguiente nodo al nuevo nodo
-        node.next = Some(Box::new(new_node));
-        // si la longitud del vector es mayor que 1
-        if data.len() > 1 {
-            // crear un nuevo vector con los datos del anterior vector
-            let mut new_data = Vec::new();
-            for i in 1..data.len() {
-                new_data.push(data[i]);
-            }
-            // llamar la función create de nuevo
-            Node::create(&mut node.next.as_mut().unwrap(), new_data);
+        // tail apunta al lugar donde va el siguiente nodo
+        let mut tail = &mut node.next;
+        for value in data {
+            let new_node = tail.insert(Box::new(Node::new(value)));
+            tail = &mut new_node.next;
         }
     }
 
     // imprimir la lista
     fn display(node: &Node) {
-        print!("{} ", node.data);
-        if node.next.is_some() {
-            Node::display(&node.next.as_ref().unwrap());
+        print!("{}", node);
+    }
+}
+
+// cada dato seguido de un espacio: "1 2 3 ". Se recorre con un ciclo y no
+// con recursion, que en una lista muy larga se queda sin pila
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut cursor = Some(self);
+        while let Some(node) = cursor {
+            write!(f, "{} ", node.data)?;
+            cursor = node.next.as_deref();
+        }
+        Ok(())
+    }
+}
+
+// sin esto, soltar un nodo suelta al siguiente de forma recursiva, y una
+// lista muy larga se queda sin pila. Aqui cada nodo se desengancha antes de
+// soltarlo, asi que se sueltan uno por uno en el ciclo
+impl Drop for Node {
+    fn drop(&mut self) {
+        let mut next = self.next.take();
+        while let Some(mut node) = next {
+            next = node.next.take();
+        }
+    }
+}
+
+// La lista solo guarda la cabeza; cada nodo es dueño del siguiente
+#[derive(Debug)]
+struct List {
+    head: Option<Box<Node>>,
+}
+
+impl List {
+    // lista vacia
+    fn new() -> List {
+        List { head: None }
+    }
+
+    // crear la lista con los datos del vector, en el mismo orden
+    fn from_vec(data: Vec<i32>) -> List {
+        let mut list = List::new();
+        // tail apunta al lugar donde va el siguiente nodo
+        let mut tail = &mut list.head;
+        for value in data {
+            let node = tail.insert(Box::new(Node::new(value)));
+            tail = &mut node.next;
+        }
+        list
+    }
+
+    // recorrer la lista sin consumirla
+    fn iter(&self) -> Iter<'_> {
+        Iter {
+            next: self.head.as_deref(),
+        }
+    }
+
+    fn to_vec(&self) -> Vec<i32> {
+        self.iter().collect()
+    }
+
+    // una lista nueva con f aplicada a cada dato; la original no cambia
+    fn map<F: Fn(i32) -> i32>(&self, f: F) -> List {
+        List::from_vec(self.iter().map(f).collect())
+    }
+
+    // combinar todos los datos en un solo valor, empezando por init
+    fn fold<B, F: Fn(B, i32) -> B>(&self, init: B, f: F) -> B {
+        let mut acc = init;
+        for data in self.iter() {
+            acc = f(acc, data);
+        }
+        acc
+    }
+
+    // una lista nueva solo con los datos que cumplen pred
+    fn filter<F: Fn(&i32) -> bool>(&self, pred: F) -> List {
+        List::from_vec(self.iter().filter(|data| pred(data)).collect())
+    }
+
+    // quitar de esta misma lista los datos que no cumplen pred
+    fn retain<F: FnMut(&i32) -> bool>(&mut self, mut pred: F) {
+        // current es el enlace que apunta al nodo que estamos revisando,
+        // al principio la cabeza de la lista
+        let mut current = &mut self.head;
+        while let Some(node) = current {
+            if pred(&node.data) {
+                current = &mut current.as_mut().unwrap().next;
+            } else {
+                // el siguiente nodo toma el lugar del que se quita, asi que
+                // no avanzamos: el nuevo nodo tambien hay que revisarlo
+                *current = node.next.take();
+            }
+        }
+    }
+
+    // partir la lista: los primeros index datos quedan en la primera mitad
+    // y el resto en la segunda. No se copia ningun nodo, solo se corta el
+    // enlace en esa posicion. Si index es mayor que la longitud, la segunda
+    // mitad queda vacia.
+    fn split_at(mut self, index: usize) -> (List, List) {
+        let mut current = &mut self.head;
+        for _ in 0..index {
+            match current {
+                Some(node) => current = &mut node.next,
+                None => break,
+            }
+        }
+        let rest = current.take();
+        (self, List { head: rest })
+    }
+
+    // rotar a la izquierda: los primeros k nodos pasan al final. Se mueven
+    // los nodos, no los datos: la cola apunta a la antigua cabeza y el nodo
+    // k + 1 pasa a ser la cabeza. Un k mayor que la longitud da la vuelta
+    // completa, por eso se usa k % longitud.
+    fn rotate(&mut self, k: usize) {
+        let len = self.iter().count();
+        if len == 0 || k.is_multiple_of(len) {
+            return;
+        }
+        // cortar la lista despues de los primeros k nodos
+        let mut current = &mut self.head;
+        for _ in 0..k % len {
+            current = &mut current.as_mut().unwrap().next;
+        }
+        let mut rest = current.take();
+        // buscar el final de la segunda parte y engancharle la primera
+        let mut tail = &mut rest;
+        while let Some(node) = tail {
+            tail = &mut node.next;
+        }
+        *tail = self.head.take();
+        self.head = rest;
+    }
+
+    // una lista nueva con los primeros n datos
+    fn take(&self, n: usize) -> List {
+        List::from_vec(self.iter().take(n).collect())
+    }
+
+    // una lista nueva sin los primeros n datos
+    fn skip(&self, n: usize) -> List {
+        List::from_vec(self.iter().skip(n).collect())
+    }
+}
+
+// unir dos listas ordenadas de menor a mayor en una sola lista ordenada.
+// Las dos listas TIENEN que estar ordenadas; si no lo estan, el resultado
+// tampoco. No se copia ningun nodo: cada nodo pasa de su lista a la nueva,
+// asi que se hace en O(n + m). Con datos iguales va primero el de a.
+fn merge_sorted(a: List, b: List) -> List {
+    let mut a = a.head;
+    let mut b = b.head;
+    let mut merged = List::new();
+    // tail apunta al lugar donde va el siguiente nodo
+    let mut tail = &mut merged.head;
+    while let (Some(x), Some(y)) = (&a, &b) {
+        // la lista cuya cabeza es la mas chica
+        let source = if x.data <= y.data { &mut a } else { &mut b };
+        let mut node = source.take().unwrap();
+        *source = node.next.take();
+        tail = &mut tail.insert(node).next;
+    }
+    // lo que queda de la otra lista ya esta ordenado, se engancha entero
+    *tail = a.or(b);
+    merged
+}
+
+struct Iter<'a> {
+    next: Option<&'a Node>,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<i32> {
+        let node = self.next?;
+        self.next = node.next.as_deref();
+        Some(node.data)
+    }
+}
+
+// dos listas son iguales si tienen los mismos datos en el mismo orden
+impl PartialEq for List {
+    fn eq(&self, other: &List) -> bool {
+        let mut a = &self.head;
+        let mut b = &other.head;
+        loop {
+            match (a, b) {
+                (Some(x), Some(y)) if x.data == y.data => {
+                    a = &x.next;
+                    b = &y.next;
+                }
+                // las dos terminaron al mismo tiempo
+                (None, None) => return true,
+                // un dato distinto, o una lista es mas larga
+                _ => return false,
+            }
+        }
+    }
+}
+
+// copia profunda: cada nodo de la copia es un Box nuevo
+impl Clone for List {
+    fn clone(&self) -> List {
+        let mut copy = List::new();
+        let mut tail = &mut copy.head;
+        let mut current = &self.head;
+        while let Some(node) = current {
+            let new_node = tail.insert(Box::new(Node::new(node.data)));
+            tail = &mut new_node.next;
+            current = &node.next;
         }
+        copy
     }
 }
 
@@ -48,4 +258,206 @@ fn main(){
     Node::create(&mut node, data);
     Node::display(&node);
     println!();
+
+    let list = List::from_vec(vec![1, 2, 3]);
+    let copy = list.clone();
+    println!("{:?} == {:?}: {}", list.to_vec(), copy.to_vec(), list == copy);
+    println!("El doble: {:?}", list.map(|x| x * 2).to_vec());
+    println!("La suma: {}", list.fold(0, |sum, x| sum + x));
+    println!("Los impares: {:?}", list.filter(|x| x % 2 != 0).to_vec());
+
+    let mut evens = List::from_vec(vec![1, 2, 3, 4, 5, 6]);
+    evens.retain(|x| x % 2 == 0);
+    println!("Los pares: {:?}", evens.to_vec());
+
+    let numbers = List::from_vec(vec![1, 2, 3, 4, 5]);
+    println!("Los dos primeros: {:?}", numbers.take(2).to_vec());
+    println!("Sin los dos primeros: {:?}", numbers.skip(2).to_vec());
+    let (left, right) = numbers.split_at(2);
+    println!("{:?} y {:?}", left.to_vec(), right.to_vec());
+    let mut rotated = List::from_vec(vec![1, 2, 3, 4, 5]);
+    rotated.rotate(2);
+    println!("Rotada: {:?}", rotated.to_vec());
+    println!("Unidas: {:?}", merge_sorted(right, left.map(|x| x * 2)).to_vec());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clone_does_not_share_nodes() {
+        let list = List::from_vec(vec![1, 2, 3]);
+        let mut copy = list.clone();
+        assert_eq!(list, copy);
+
+        // cambiar la copia no cambia la original
+        copy.head.as_mut().unwrap().next.as_mut().unwrap().data = 20;
+        copy.head.as_mut().unwrap().next = None;
+        assert_eq!(vec![1, 2, 3], list.to_vec());
+        assert_eq!(vec![1], copy.to_vec());
+        assert_ne!(list, copy);
+    }
+
+    #[test]
+    fn map_and_fold_keep_the_original() {
+        let list = List::from_vec(vec![1, 2, 3]);
+
+        assert_eq!(List::from_vec(vec![2, 4, 6]), list.map(|x| x * 2));
+        assert_eq!(6, list.fold(0, |sum, x| sum + x));
+        assert_eq!(
+            String::from("123"),
+            list.fold(String::new(), |s, x| s + &x.to_string())
+        );
+        assert_eq!(vec![1, 2, 3], list.to_vec());
+
+        assert_eq!(List::new(), List::new().map(|x| x * 2));
+        assert_eq!(0, List::new().fold(0, |sum, x| sum + x));
+    }
+
+    #[test]
+    fn filter_keeps_the_even_numbers() {
+        let list = List::from_vec(vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(List::from_vec(vec![2, 4, 6]), list.filter(|x| x % 2 == 0));
+        assert_eq!(vec![1, 2, 3, 4, 5, 6], list.to_vec());
+    }
+
+    #[test]
+    fn retain_removes_nodes_in_place() {
+        let mut list = List::from_vec(vec![1, 2, 3, 4, 5, 6]);
+        list.retain(|x| x % 2 == 0);
+        assert_eq!(vec![2, 4, 6], list.to_vec());
+
+        // quitar la cabeza y varios nodos seguidos
+        let mut list = List::from_vec(vec![1, 1, 3, 4, 5, 7, 8]);
+        list.retain(|x| x % 2 == 0);
+        assert_eq!(vec![4, 8], list.to_vec());
+
+        let mut list = List::from_vec(vec![1, 3, 5]);
+        list.retain(|x| x % 2 == 0);
+        assert_eq!(List::new(), list);
+    }
+
+    #[test]
+    fn split_at_moves_the_nodes() {
+        let list = List::from_vec(vec![1, 2, 3, 4, 5]);
+        let second = list.head.as_ref().unwrap().next.as_ref().unwrap();
+        let third: *const Node = second.next.as_deref().unwrap();
+
+        let (left, right) = list.split_at(2);
+        assert_eq!(vec![1, 2], left.to_vec());
+        assert_eq!(vec![3, 4, 5], right.to_vec());
+        // la segunda mitad empieza con el mismo nodo, no con una copia
+        assert!(std::ptr::eq(third, right.head.as_deref().unwrap()));
+    }
+
+    #[test]
+    fn split_at_the_edges() {
+        let (left, right) = List::from_vec(vec![1, 2, 3]).split_at(0);
+        assert_eq!(List::new(), left);
+        assert_eq!(vec![1, 2, 3], right.to_vec());
+
+        let (left, right) = List::from_vec(vec![1, 2, 3]).split_at(3);
+        assert_eq!(vec![1, 2, 3], left.to_vec());
+        assert_eq!(List::new(), right);
+
+        let (left, right) = List::from_vec(vec![1, 2, 3]).split_at(10);
+        assert_eq!(vec![1, 2, 3], left.to_vec());
+        assert_eq!(List::new(), right);
+    }
+
+    #[test]
+    fn take_and_skip() {
+        let list = List::from_vec(vec![1, 2, 3, 4, 5]);
+        assert_eq!(List::from_vec(vec![1, 2]), list.take(2));
+        assert_eq!(List::from_vec(vec![3, 4, 5]), list.skip(2));
+        assert_eq!(list, list.take(10));
+        assert_eq!(List::new(), list.skip(10));
+    }
+
+    #[test]
+    fn merge_sorted_interleaves_the_nodes() {
+        let a = List::from_vec(vec![1, 3, 5]);
+        let b = List::from_vec(vec![2, 4, 6]);
+        let first_of_b: *const Node = b.head.as_deref().unwrap();
+
+        let merged = merge_sorted(a, b);
+        assert_eq!(vec![1, 2, 3, 4, 5, 6], merged.to_vec());
+        // el segundo nodo es el mismo que era la cabeza de b
+        let second = merged.head.as_ref().unwrap().next.as_deref().unwrap();
+        assert!(std::ptr::eq(first_of_b, second));
+    }
+
+    #[test]
+    fn merge_sorted_with_uneven_lists() {
+        let merged = merge_sorted(List::from_vec(vec![1, 2, 2, 9]), List::from_vec(vec![2, 3]));
+        assert_eq!(vec![1, 2, 2, 2, 3, 9], merged.to_vec());
+
+        let merged = merge_sorted(List::from_vec(vec![4, 5]), List::from_vec(vec![1]));
+        assert_eq!(vec![1, 4, 5], merged.to_vec());
+    }
+
+    #[test]
+    fn merge_sorted_with_an_empty_list() {
+        let merged = merge_sorted(List::from_vec(vec![1, 3, 5]), List::new());
+        assert_eq!(vec![1, 3, 5], merged.to_vec());
+
+        let merged = merge_sorted(List::new(), List::from_vec(vec![2, 4]));
+        assert_eq!(vec![2, 4], merged.to_vec());
+
+        assert_eq!(List::new(), merge_sorted(List::new(), List::new()));
+    }
+
+    #[test]
+    fn rotate_moves_the_first_nodes_to_the_end() {
+        let mut list = List::from_vec(vec![1, 2, 3, 4, 5]);
+        let second = list.head.as_ref().unwrap().next.as_ref().unwrap();
+        let third: *const Node = second.next.as_deref().unwrap();
+        list.rotate(2);
+        assert_eq!(vec![3, 4, 5, 1, 2], list.to_vec());
+        // la nueva cabeza es el mismo nodo que era el tercero
+        assert!(std::ptr::eq(third, list.head.as_deref().unwrap()));
+
+        // dar mas de una vuelta
+        list.rotate(13);
+        assert_eq!(vec![1, 2, 3, 4, 5], list.to_vec());
+    }
+
+    #[test]
+    fn rotate_by_zero_or_the_length_changes_nothing() {
+        let mut list = List::from_vec(vec![1, 2, 3, 4, 5]);
+        list.rotate(0);
+        assert_eq!(vec![1, 2, 3, 4, 5], list.to_vec());
+        list.rotate(5);
+        assert_eq!(vec![1, 2, 3, 4, 5], list.to_vec());
+
+        let mut empty = List::new();
+        empty.rotate(3);
+        assert_eq!(List::new(), empty);
+    }
+
+    #[test]
+    fn long_lists_are_displayed_without_recursion() {
+        let mut node = Node::new(0);
+        Node::create(&mut node, (1..200_000).collect());
+
+        // el mismo formato de siempre: cada dato seguido de un espacio
+        let expected: String = (0..200_000).map(|data| format!("{} ", data)).collect();
+        assert_eq!(expected, node.to_string());
+
+        assert_eq!("7 ", Node::new(7).to_string());
+    }
+
+    #[test]
+    fn lists_with_the_same_values_are_equal() {
+        let mut built = List::new();
+        built.head = Some(Box::new(Node::new(1)));
+        Node::create(built.head.as_mut().unwrap(), vec![2, 3]);
+
+        assert_eq!(List::from_vec(vec![1, 2, 3]), built);
+        assert_ne!(List::from_vec(vec![1, 2]), built);
+        assert_ne!(List::from_vec(vec![1, 2, 3, 4]), built);
+        assert_ne!(List::from_vec(vec![1, 2, 4]), built);
+        assert_eq!(List::new(), List::from_vec(vec![]));
+    }
 }
@@ -1,14 +1,142 @@
-fn tower_of_hanoi(x: i32, from: i32, aux: i32, to: i32) {
-    if x > 0 {
-        tower_of_hanoi(x - 1, from, to, aux);
-        println!("From {} to {}", from, to);
-        tower_of_hanoi(x - 1, aux, from, to);
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+enum HanoiError {
+    // the pegs are numbered 0, 1 and 2
+    PegOutOfRange(u8),
+    // from, aux and to must be three different pegs
+    SamePeg(u8),
+}
+
+impl fmt::Display for HanoiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HanoiError::PegOutOfRange(peg) => write!(f, "there is no peg {}, only 0 to 2", peg),
+            HanoiError::SamePeg(peg) => write!(f, "peg {} is used twice", peg),
+        }
+    }
+}
+
+// The moves that take n disks from one peg to another, as (from, to) pairs.
+fn hanoi(n: u32, from: u8, aux: u8, to: u8) -> Result<Vec<(u8, u8)>, HanoiError> {
+    for peg in [from, aux, to] {
+        if peg > 2 {
+            return Err(HanoiError::PegOutOfRange(peg));
+        }
+    }
+    if from == aux || from == to {
+        return Err(HanoiError::SamePeg(from));
+    }
+    if aux == to {
+        return Err(HanoiError::SamePeg(aux));
     }
+
+    fn moves(x: u32, from: u8, aux: u8, to: u8, result: &mut Vec<(u8, u8)>) {
+        if x > 0 {
+            moves(x - 1, from, to, aux, result);
+            result.push((from, to));
+            moves(x - 1, aux, from, to, result);
+        }
+    }
+
+    let mut result = Vec::new();
+    moves(n, from, aux, to, &mut result);
+    Ok(result)
+}
+
+// The pegs after every move, with each peg listed from bottom to top.
+// The first state has all n disks on peg 0, numbered n (the largest) down
+// to 1, so there are 2^n states in total.
+fn hanoi_states(n: u32) -> Vec<[Vec<u32>; 3]> {
+    let mut states = vec![[(1..=n).rev().collect(), Vec::new(), Vec::new()]];
+    for (from, to) in hanoi(n, 0, 1, 2).unwrap() {
+        let mut pegs = states.last().unwrap().clone();
+        let disk = pegs[from as usize].pop().unwrap();
+        pegs[to as usize].push(disk);
+        states.push(pegs);
+    }
+    states
 }
 
 fn main() {
     let x = 3;
-    tower_of_hanoi(x, 1, 2, 3);
+    match hanoi(x, 0, 1, 2) {
+        Ok(moves) => {
+            for (from, to) in moves {
+                println!("From {} to {}", from, to);
+            }
+        }
+        Err(e) => println!("Invalid pegs: {}", e),
+    }
+
+    for pegs in hanoi_states(2) {
+        println!("{:?}", pegs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn larger_disk_never_on_a_smaller_one() {
+        for n in 0..=6 {
+            let states = hanoi_states(n);
+            assert_eq!(2usize.pow(n), states.len());
+
+            for pegs in &states {
+                for peg in pegs {
+                    assert!(peg.windows(2).all(|pair| pair[0] > pair[1]));
+                }
+                assert_eq!(n as usize, pegs.iter().map(Vec::len).sum::<usize>());
+            }
+            // every move takes one disk from one peg to another
+            for pair in states.windows(2) {
+                let changed = (0..3).filter(|&i| pair[0][i] != pair[1][i]).count();
+                assert_eq!(2, changed);
+            }
+
+            let all: Vec<u32> = (1..=n).rev().collect();
+            assert_eq!(&[Vec::new(), Vec::new(), all], states.last().unwrap());
+        }
+    }
+
+    #[test]
+    fn moves_for_three_disks() {
+        assert_eq!(
+            Ok(vec![(0, 2), (0, 1), (2, 1), (0, 2), (1, 0), (1, 2), (0, 2)]),
+            hanoi(3, 0, 1, 2)
+        );
+        assert_eq!(Ok(vec![(2, 0)]), hanoi(1, 2, 1, 0));
+    }
+
+    #[test]
+    fn no_disks_no_moves() {
+        assert_eq!(Ok(vec![]), hanoi(0, 0, 1, 2));
+    }
+
+    #[test]
+    fn invalid_pegs() {
+        assert_eq!(Err(HanoiError::SamePeg(0)), hanoi(3, 0, 0, 2));
+        assert_eq!(Err(HanoiError::SamePeg(0)), hanoi(3, 0, 1, 0));
+        assert_eq!(Err(HanoiError::SamePeg(1)), hanoi(3, 0, 1, 1));
+        assert_eq!(Err(HanoiError::PegOutOfRange(3)), hanoi(3, 1, 2, 3));
+        // also checked when there is nothing to move
+        assert_eq!(Err(HanoiError::SamePeg(2)), hanoi(0, 2, 2, 2));
+    }
+
+    #[test]
+    fn two_disks() {
+        assert_eq!(
+            vec![
+                [vec![2, 1], vec![], vec![]],
+                [vec![2], vec![1], vec![]],
+                [vec![], vec![1], vec![2]],
+                [vec![], vec![], vec![2, 1]],
+            ],
+            hanoi_states(2)
+        );
+    }
 }
 
 /*
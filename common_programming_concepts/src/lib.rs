@@ -0,0 +1,3 @@
+pub mod math;
+pub mod numbers;
+pub mod units;
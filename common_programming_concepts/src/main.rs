@@ -1,3 +1,5 @@
+use common_programming_concepts::{math, numbers, units};
+
 fn main() {
     // Variables
     let mut x = 5;
@@ -7,6 +9,7 @@ fn main() {
     // Constants
     const THREE_HOURS_IN_SECONDS: u32 = 60 * 60 * 3;
     println!("{}", THREE_HOURS_IN_SECONDS);
+    println!("{:?}", units::hours_to_seconds(3));
     // Shadowing
     let y = 7;
     let y = y + 1;
@@ -54,7 +57,7 @@ fn main() {
     };
     // Functions with return types
     let _five = five();
-    let _plus_one = plus_one(7);
+    let _plus_one = math::plus_one(7);
     // Comments
     // This is comment
     /* This is a multiline comment
@@ -79,6 +82,8 @@ fn main() {
     } else {
         println!("number is not divisible by 4, 3, or 2");
     }
+    // the same chain, as a reusable function
+    println!("{}", numbers::classify(number));
     // Ternerary Operator
     let condition = true;
     let number = if condition { 5 } else { 6 };
@@ -111,6 +116,10 @@ fn main() {
     for element in a {
         println!("the value is: {}", element);
     }
+    // for loop over an iterator
+    for said in numbers::fizzbuzz(15) {
+        println!("{}", said);
+    }
 }
 
 fn another_function() {
@@ -123,8 +132,4 @@ fn print_labeled_measurement(value: i32, unit_label: char) {
 
 fn five() -> i32 {
     5
-}
-
-fn plus_one(x: i32) -> i32 {
-    x + 1
 }
\ No newline at end of file
@@ -0,0 +1,55 @@
+use std::ops::Add;
+
+/// Returns `x + 1`.
+///
+/// # Panics
+///
+/// Like any `i32` addition, this panics on overflow in debug builds. Use
+/// [`checked_plus_one`] when `x` may be `i32::MAX`.
+pub fn plus_one(x: i32) -> i32 {
+    x + 1
+}
+
+/// Adds two values of any type that implements `Add`.
+pub fn add<T: Add<Output = T>>(a: T, b: T) -> T {
+    a + b
+}
+
+/// Returns `x + 1`, or `None` if that would overflow.
+pub fn checked_plus_one(x: i32) -> Option<i32> {
+    x.checked_add(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plus_one_adds_one() {
+        assert_eq!(8, plus_one(7));
+        assert_eq!(0, plus_one(-1));
+        assert_eq!(i32::MAX, plus_one(i32::MAX - 1));
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn plus_one_panics_past_max() {
+        plus_one(i32::MAX);
+    }
+
+    #[test]
+    fn add_works_for_several_types() {
+        assert_eq!(15, add(5, 10));
+        assert_eq!(3.0, add(2.5, 0.5));
+        assert_eq!(300_u64, add(100_u64, 200));
+    }
+
+    #[test]
+    fn checked_plus_one_stops_at_max() {
+        assert_eq!(Some(8), checked_plus_one(7));
+        assert_eq!(Some(i32::MAX), checked_plus_one(i32::MAX - 1));
+        assert_eq!(None, checked_plus_one(i32::MAX));
+        assert_eq!(Some(i32::MIN + 1), checked_plus_one(i32::MIN));
+    }
+}
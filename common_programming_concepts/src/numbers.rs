@@ -0,0 +1,89 @@
+/// Returns whether `d` divides `n` evenly. Nothing is divisible by zero,
+/// so `d == 0` gives `false` instead of panicking.
+pub fn is_divisible_by(n: i32, d: i32) -> bool {
+    // wrapping_rem also avoids the overflow in i32::MIN % -1
+    d != 0 && n.wrapping_rem(d) == 0
+}
+
+pub fn is_even(n: i32) -> bool {
+    is_divisible_by(n, 2)
+}
+
+pub fn is_odd(n: i32) -> bool {
+    !is_even(n)
+}
+
+/// Describes the largest of 4, 3 and 2 that divides `n`, like the
+/// `else if` example in `main`.
+pub fn classify(n: i32) -> &'static str {
+    if is_divisible_by(n, 4) {
+        "number is divisible by 4"
+    } else if is_divisible_by(n, 3) {
+        "number is divisible by 3"
+    } else if is_divisible_by(n, 2) {
+        "number is divisible by 2"
+    } else {
+        "number is not divisible by 4, 3, or 2"
+    }
+}
+
+/// Counts from 1 to `n`, saying "Fizz" for multiples of 3, "Buzz" for
+/// multiples of 5 and "FizzBuzz" for multiples of both.
+pub fn fizzbuzz(n: u32) -> impl Iterator<Item = String> {
+    (1..=n).map(|i| match (i % 3, i % 5) {
+        (0, 0) => String::from("FizzBuzz"),
+        (0, _) => String::from("Fizz"),
+        (_, 0) => String::from("Buzz"),
+        _ => i.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn divisibility() {
+        assert!(is_divisible_by(12, 4));
+        assert!(is_divisible_by(-9, 3));
+        assert!(is_divisible_by(0, 7));
+        assert!(!is_divisible_by(7, 2));
+        assert!(is_divisible_by(i32::MIN, -1));
+    }
+
+    #[test]
+    fn nothing_is_divisible_by_zero() {
+        assert!(!is_divisible_by(0, 0));
+        assert!(!is_divisible_by(5, 0));
+    }
+
+    #[test]
+    fn even_and_odd() {
+        assert!(is_even(0));
+        assert!(is_even(-4));
+        assert!(is_odd(-3));
+        assert!(is_odd(i32::MAX));
+        assert!(!is_odd(6));
+    }
+
+    #[test]
+    fn classify_picks_the_largest_divisor() {
+        assert_eq!("number is divisible by 4", classify(8));
+        assert_eq!("number is divisible by 3", classify(6));
+        assert_eq!("number is divisible by 2", classify(10));
+        assert_eq!("number is not divisible by 4, 3, or 2", classify(7));
+    }
+
+    #[test]
+    fn fizzbuzz_classic() {
+        let said: Vec<String> = fizzbuzz(15).collect();
+        assert_eq!(
+            vec![
+                "1", "2", "Fizz", "4", "Buzz", "Fizz", "7", "8", "Fizz", "Buzz", "11", "Fizz",
+                "13", "14", "FizzBuzz"
+            ],
+            said
+        );
+        assert_eq!(0, fizzbuzz(0).count());
+    }
+}
@@ -0,0 +1,48 @@
+/// Converts hours to seconds, like `THREE_HOURS_IN_SECONDS` in `main`, or
+/// returns `None` if the result doesn't fit in a `u32`.
+pub fn hours_to_seconds(h: u32) -> Option<u32> {
+    h.checked_mul(60 * 60)
+}
+
+pub fn celsius_to_fahrenheit(c: f64) -> f64 {
+    c * 9.0 / 5.0 + 32.0
+}
+
+pub fn fahrenheit_to_celsius(f: f64) -> f64 {
+    (f - 32.0) * 5.0 / 9.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hours_to_seconds_matches_the_constant() {
+        assert_eq!(Some(60 * 60 * 3), hours_to_seconds(3));
+        assert_eq!(Some(0), hours_to_seconds(0));
+    }
+
+    #[test]
+    fn hours_to_seconds_overflow() {
+        let max_hours = u32::MAX / 3600;
+        assert_eq!(Some(max_hours * 3600), hours_to_seconds(max_hours));
+        assert_eq!(None, hours_to_seconds(max_hours + 1));
+        assert_eq!(None, hours_to_seconds(u32::MAX));
+    }
+
+    #[test]
+    fn known_temperatures() {
+        assert_eq!(32.0, celsius_to_fahrenheit(0.0));
+        assert_eq!(212.0, celsius_to_fahrenheit(100.0));
+        assert_eq!(-40.0, celsius_to_fahrenheit(-40.0));
+        assert_eq!(0.0, fahrenheit_to_celsius(32.0));
+        assert_eq!(100.0, fahrenheit_to_celsius(212.0));
+    }
+
+    #[test]
+    fn temperatures_round_trip() {
+        for c in [-273.15, -17.5, 0.0, 36.6, 1000.0] {
+            assert!((c - fahrenheit_to_celsius(celsius_to_fahrenheit(c))).abs() < 1e-9);
+        }
+    }
+}
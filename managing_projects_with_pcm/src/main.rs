@@ -1,5 +1,7 @@
 // This will allow dead code in the whole crate
 #![allow(dead_code)]
+// Some of the use examples below are only there to show the syntax
+#![allow(unused_imports)]
 use std::collections::HashMap;
 // The parent modules distinguishes the two Result types
 use std::fmt;
@@ -95,4 +97,7 @@ pub fn eat_at_restaurant() {
 fn main() {
     let mut map = HashMap::new();
     map.insert(1, 2);
+    // Counting words is a common use for a HashMap
+    let counts = managing_projects_with_pcm::words::word_count("the cat and the hat");
+    println!("{:?}", counts.get("the"));
 }
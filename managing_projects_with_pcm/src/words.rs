@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+/// Counts how often each word appears in `text`.
+///
+/// Words are split on whitespace, lowercased, and stripped of any punctuation
+/// around them, so `"Nobody!"` and `"nobody"` count as the same word.
+/// Punctuation inside a word, like the apostrophe in `"don't"`, is kept.
+pub fn word_count(text: &str) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+
+    for word in text.split_whitespace() {
+        let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+        if word.is_empty() {
+            continue;
+        }
+        let count = counts.entry(word.to_lowercase()).or_insert(0);
+        *count += 1;
+    }
+
+    counts
+}
+
+/// Returns the `n` most frequent words in `text` with their counts.
+///
+/// Words are counted like in [`word_count`]. The result is sorted by count,
+/// highest first; words with the same count are sorted alphabetically so the
+/// order is always the same.
+pub fn top_words(text: &str, n: usize) -> Vec<(String, usize)> {
+    let mut words: Vec<(String, usize)> = word_count(text).into_iter().collect();
+    words.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+    words.truncate(n);
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_repeated_and_punctuated_words() {
+        let counts = word_count("I'm nobody! Who are you? Are you nobody, too? (Nobody.)");
+
+        assert_eq!(Some(&3), counts.get("nobody"));
+        assert_eq!(Some(&2), counts.get("are"));
+        assert_eq!(Some(&2), counts.get("you"));
+        assert_eq!(Some(&1), counts.get("i'm"));
+        assert_eq!(Some(&1), counts.get("too"));
+        assert_eq!(6, counts.len());
+    }
+
+    #[test]
+    fn skips_lone_punctuation() {
+        let counts = word_count("us - don't tell !");
+
+        assert_eq!(3, counts.len());
+        assert_eq!(Some(&1), counts.get("don't"));
+        assert_eq!(None, counts.get("-"));
+    }
+
+    #[test]
+    fn empty_text_has_no_words() {
+        assert!(word_count("").is_empty());
+        assert!(word_count("  \n\t ").is_empty());
+    }
+
+    #[test]
+    fn top_words_sorts_by_count_then_alphabetically() {
+        let text = "b a c b a d b c e";
+
+        assert_eq!(
+            vec![
+                (String::from("b"), 3),
+                (String::from("a"), 2),
+                (String::from("c"), 2),
+                (String::from("d"), 1),
+            ],
+            top_words(text, 4)
+        );
+    }
+
+    #[test]
+    fn top_words_with_fewer_words_than_asked() {
+        assert_eq!(
+            vec![(String::from("nobody"), 2)],
+            top_words("Nobody, nobody.", 10)
+        );
+        assert!(top_words("anything", 0).is_empty());
+    }
+}
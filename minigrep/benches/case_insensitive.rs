@@ -0,0 +1,42 @@
+//! Compares `search_case_insensitive` with lowercasing every line before
+//! looking for the query.
+//!
+//! Run with `cargo bench --bench case_insensitive`.
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use minigrep::search_case_insensitive;
+
+fn naive<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
+    let query = query.to_lowercase();
+    contents
+        .lines()
+        .filter(|line| line.to_lowercase().contains(&query))
+        .collect()
+}
+
+fn case_insensitive(c: &mut Criterion) {
+    // one line in ten contains the query
+    let ascii: String = (0..10_000)
+        .map(|i| {
+            if i % 10 == 0 {
+                format!("GET /search?q=NEEDLE 200 in {}ms\n", i % 97)
+            } else {
+                format!("GET /static/app.{}.js 200 in {}ms\n", i, i % 97)
+            }
+        })
+        .collect();
+    let accented = ascii.replace("static", "statíc");
+
+    for (name, contents) in [("ascii", &ascii), ("non-ascii", &accented)] {
+        let mut group = c.benchmark_group(format!("10000 {} lines", name));
+        group.bench_function("search_case_insensitive", |b| {
+            b.iter(|| search_case_insensitive(black_box("needle"), black_box(contents)).len())
+        });
+        group.bench_function("lowercase every line", |b| {
+            b.iter(|| naive(black_box("needle"), black_box(contents)).len())
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, case_insensitive);
+criterion_main!(benches);
@@ -0,0 +1,113 @@
+//! Measures `is_match` of every `Matcher` on a few kinds of input, so that
+//! changes to one of them can be compared before and after.
+//!
+//! Run with `cargo bench --bench matchers`, or pick out a profile or a
+//! matcher with a filter, like `cargo bench --bench matchers -- "long lines"`
+//! or `cargo bench --bench matchers -- regex`. Criterion keeps the last run
+//! under `target/criterion` and reports the change against it.
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use minigrep::matcher::{Matcher, RegexMatcher, SubstringMatcher, WholeWordMatcher};
+
+// What the lines of a profile look like, and how many of them match.
+struct Profile {
+    name: &'static str,
+    lines: Vec<String>,
+}
+
+fn profiles() -> Vec<Profile> {
+    let log_line = |i: usize, path: &str| format!("GET /{} 200 in {}ms", path, i % 97);
+    vec![
+        // one short line in a hundred matches
+        Profile {
+            name: "short lines, few matches",
+            lines: (0..10_000)
+                .map(|i| match i % 100 {
+                    0 => log_line(i, "search?q=needle"),
+                    _ => log_line(i, &format!("static/app.{}.js", i)),
+                })
+                .collect(),
+        },
+        // every other short line matches
+        Profile {
+            name: "short lines, many matches",
+            lines: (0..10_000)
+                .map(|i| match i % 2 {
+                    0 => log_line(i, "search?q=needle"),
+                    _ => log_line(i, &format!("static/app.{}.js", i)),
+                })
+                .collect(),
+        },
+        // 4 KiB lines, one in a hundred has a match at its very end
+        Profile {
+            name: "long lines, few matches",
+            lines: (0..250)
+                .map(|i| {
+                    let mut line = "lorem ipsum dolor sit amet ".repeat(150);
+                    if i % 100 == 0 {
+                        line.push_str("needle");
+                    }
+                    line
+                })
+                .collect(),
+        },
+    ]
+}
+
+fn patterns(patterns: &[&str]) -> Vec<String> {
+    patterns.iter().map(|p| p.to_string()).collect()
+}
+
+fn matchers() -> Vec<(&'static str, Box<dyn Matcher>)> {
+    let many: Vec<String> = (0..100)
+        .map(|i| format!("needle{:02}", i))
+        .chain(["needle".to_string()])
+        .collect();
+    vec![
+        (
+            "substring",
+            Box::new(SubstringMatcher::new(&patterns(&["needle"]), true).unwrap()),
+        ),
+        (
+            "substring, ignoring case",
+            Box::new(SubstringMatcher::new(&patterns(&["NEEDLE"]), false).unwrap()),
+        ),
+        (
+            "substring, 101 patterns",
+            Box::new(SubstringMatcher::new(&many, true).unwrap()),
+        ),
+        (
+            "regex",
+            Box::new(RegexMatcher::new(&patterns(&[r"q=ne+dle\b"]), true).unwrap()),
+        ),
+        (
+            "whole word",
+            Box::new(WholeWordMatcher::new(Box::new(
+                SubstringMatcher::new(&patterns(&["needle"]), true).unwrap(),
+            ))),
+        ),
+    ]
+}
+
+fn is_match(c: &mut Criterion) {
+    let matchers = matchers();
+    for profile in profiles() {
+        let bytes: usize = profile.lines.iter().map(|line| line.len() + 1).sum();
+        let mut group = c.benchmark_group(profile.name);
+        group.throughput(Throughput::Bytes(bytes as u64));
+        for (name, matcher) in &matchers {
+            group.bench_function(*name, |b| {
+                b.iter(|| {
+                    profile
+                        .lines
+                        .iter()
+                        .filter(|line| matcher.is_match(black_box(line)))
+                        .count()
+                })
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, is_match);
+criterion_main!(benches);
@@ -0,0 +1,48 @@
+//! Compares the Aho-Corasick backend with looping over `contains` for many
+//! literal patterns.
+//!
+//! Run with `cargo bench --bench patterns`.
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use minigrep::patterns::MultiMatcher;
+
+fn many_patterns(c: &mut Criterion) {
+    // fixed width, so no pattern is a prefix of another
+    let patterns: Vec<String> = (0..1000).map(|i| format!("needle{:04}", i)).collect();
+    // one line in ten contains a pattern
+    let lines: Vec<String> = (0..1000)
+        .map(|i| {
+            if i % 10 == 0 {
+                format!("GET /search?q=needle{:04} 200 in {}ms", i, i % 97)
+            } else {
+                format!("GET /static/app.{}.js 200 in {}ms", i, i % 97)
+            }
+        })
+        .collect();
+    let matcher = MultiMatcher::new(&patterns, true).unwrap();
+
+    let mut group = c.benchmark_group("1000 patterns");
+    group.bench_function("aho-corasick", |b| {
+        b.iter(|| {
+            lines
+                .iter()
+                .filter(|line| matcher.is_match(black_box(line)))
+                .count()
+        })
+    });
+    group.bench_function("contains loop", |b| {
+        b.iter(|| {
+            lines
+                .iter()
+                .filter(|line| {
+                    patterns
+                        .iter()
+                        .any(|pattern| black_box(line).contains(pattern.as_str()))
+                })
+                .count()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, many_patterns);
+criterion_main!(benches);
@@ -0,0 +1,236 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+/// What a field in front of a line holds, which decides how [`align`] pads
+/// it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// A file name, padded after its separator.
+    Name,
+    /// A line number, count or byte offset, padded in front.
+    Number,
+}
+
+// While the output is buffered, a line written with `write_fields` starts
+// with a NUL, which no file name or argument can hold. Each field follows
+// as its kind, then the field and its separator with their lengths in
+// front, and END comes before the text. Nothing in a field is ever taken
+// for a marker, whatever bytes it holds.
+const ROW: u8 = 0;
+const NAME: u8 = b'a';
+const NUMBER: u8 = b'#';
+const END: u8 = b'.';
+
+/// Starts a line with `fields`, each with the separator that follows it,
+/// so that [`align`] can pad them. The text of the line comes after.
+pub fn write_fields(out: &mut dyn Write, fields: &[(Field, &str, &str)]) -> io::Result<()> {
+    out.write_all(&[ROW])?;
+    for &(kind, field, separator) in fields {
+        out.write_all(&[match kind {
+            Field::Name => NAME,
+            Field::Number => NUMBER,
+        }])?;
+        for part in [field, separator] {
+            out.write_all(&(part.len() as u32).to_le_bytes())?;
+            out.write_all(part.as_bytes())?;
+        }
+    }
+    out.write_all(&[END])
+}
+
+// One line of the buffered output: its fields, if it was written with
+// `write_fields`, and the rest of it.
+struct Line<'a> {
+    fields: Vec<(Field, Cow<'a, str>, Cow<'a, str>)>,
+    text: &'a [u8],
+    terminated: bool,
+}
+
+/// Writes `output`, a search buffered for `--align`, to `out` with every
+/// field padded to the widest one in its column.
+///
+/// File names are padded after their separator and numbers in front of
+/// them, so that both the numbers and the text start in the same column:
+///
+/// ```text
+/// a.txt:       3:I'm nobody!
+/// poems/b.txt:12:Are you nobody, too?
+/// ```
+///
+/// Lines without fields, like `--` between groups, are written as they
+/// are. `terminator` can't be a NUL, which starts the lines with fields.
+pub fn align(output: &[u8], terminator: u8, out: &mut dyn Write) -> io::Result<()> {
+    let mut lines = Vec::new();
+    let mut rest = output;
+    while !rest.is_empty() {
+        let (line, after) = parse_line(rest, terminator)?;
+        lines.push(line);
+        rest = after;
+    }
+
+    let columns = lines
+        .iter()
+        .map(|line| line.fields.len())
+        .max()
+        .unwrap_or(0);
+    let widths: Vec<_> = (0..columns)
+        .map(|i| {
+            let column: Vec<&str> = lines
+                .iter()
+                .filter_map(|line| line.fields.get(i))
+                .map(|(_, field, _)| field.as_ref())
+                .collect();
+            max_line_width(&column)
+        })
+        .collect();
+
+    for line in &lines {
+        for ((kind, field, separator), width) in line.fields.iter().zip(&widths) {
+            let padding = width - field.chars().count();
+            match kind {
+                Field::Number => write!(out, "{:padding$}{}{}", "", field, separator)?,
+                Field::Name => write!(out, "{}{}{:padding$}", field, separator, "")?,
+            }
+        }
+        out.write_all(line.text)?;
+        if line.terminated {
+            out.write_all(&[terminator])?;
+        }
+    }
+    Ok(())
+}
+
+// Splits the first line off `output`, returning it and what comes after.
+fn parse_line(output: &[u8], terminator: u8) -> io::Result<(Line<'_>, &[u8])> {
+    let mut fields = Vec::new();
+    let mut rest = output;
+    if let Some((&ROW, after)) = rest.split_first() {
+        rest = after;
+        loop {
+            let (&kind, after) = rest.split_first().ok_or_else(malformed)?;
+            rest = after;
+            let kind = match kind {
+                NAME => Field::Name,
+                NUMBER => Field::Number,
+                END => break,
+                _ => return Err(malformed()),
+            };
+            let (field, after) = take_part(rest)?;
+            let (separator, after) = take_part(after)?;
+            fields.push((kind, field, separator));
+            rest = after;
+        }
+    }
+    let (text, rest, terminated) = match rest.iter().position(|&b| b == terminator) {
+        Some(end) => (&rest[..end], &rest[end + 1..], true),
+        None => (rest, &rest[rest.len()..], false),
+    };
+    let line = Line {
+        fields,
+        text,
+        terminated,
+    };
+    Ok((line, rest))
+}
+
+// A field or separator and the length in front of it.
+fn take_part(bytes: &[u8]) -> io::Result<(Cow<'_, str>, &[u8])> {
+    let (len, rest) = bytes.split_first_chunk::<4>().ok_or_else(malformed)?;
+    let len = u32::from_le_bytes(*len) as usize;
+    if rest.len() < len {
+        return Err(malformed());
+    }
+    let (part, rest) = rest.split_at(len);
+    Ok((String::from_utf8_lossy(part), rest))
+}
+
+fn malformed() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "malformed --align buffer")
+}
+
+// The width of the widest of `results`, in characters.
+fn max_line_width(results: &[&str]) -> usize {
+    results
+        .iter()
+        .map(|result| result.chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Field::{Name, Number};
+
+    type Fields<'a> = &'a [(Field, &'a str)];
+
+    fn aligned(lines: &[(Fields, &str, &str)]) -> String {
+        let mut buffered = Vec::new();
+        for &(fields, separator, text) in lines {
+            if !fields.is_empty() {
+                let fields: Vec<_> = fields.iter().map(|&(k, f)| (k, f, separator)).collect();
+                write_fields(&mut buffered, &fields).unwrap();
+            }
+            writeln!(buffered, "{}", text).unwrap();
+        }
+        let mut out = Vec::new();
+        align(&buffered, b'\n', &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn widest_line_in_characters() {
+        assert_eq!(6, max_line_width(&["a.txt", "cafés!", "b"]));
+        assert_eq!(0, max_line_width(&[]));
+    }
+
+    #[test]
+    fn file_names_and_numbers_line_up() {
+        assert_eq!(
+            "\
+a.txt:           3:I'm nobody!
+poems/long.txt: 12:Are you nobody, too?
+poems/long.txt- 13-How dreary
+--
+b.txt:         100:Then there's a pair of us
+",
+            aligned(&[
+                (&[(Name, "a.txt"), (Number, "3")], ":", "I'm nobody!"),
+                (
+                    &[(Name, "poems/long.txt"), (Number, "12")],
+                    ":",
+                    "Are you nobody, too?"
+                ),
+                (
+                    &[(Name, "poems/long.txt"), (Number, "13")],
+                    "-",
+                    "How dreary"
+                ),
+                (&[], "", "--"),
+                (
+                    &[(Name, "b.txt"), (Number, "100")],
+                    ":",
+                    "Then there's a pair of us"
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn file_names_are_never_taken_for_numbers_or_markers() {
+        assert_eq!(
+            "\
+10:   3:I'm nobody!
+a\u{1f}\u{1e}b:12:Are you\u{1e} nobody, too?
+",
+            aligned(&[
+                (&[(Name, "10"), (Number, "3")], ":", "I'm nobody!"),
+                (
+                    &[(Name, "a\u{1f}\u{1e}b"), (Number, "12")],
+                    ":",
+                    "Are you\u{1e} nobody, too?"
+                ),
+            ])
+        );
+    }
+}
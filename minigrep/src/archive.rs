@@ -0,0 +1,86 @@
+use crate::source::{Source, StringSource};
+use std::io::{self, Read, Seek};
+use zip::ZipArchive;
+
+/// Turns the text files in a ZIP archive into sources for
+/// [`crate::run_sources`], for `--zip`.
+///
+/// Each is named `archive!entry` after the archive's `name` and the path
+/// inside it, like `logs.zip!2024/app.log`. Directories are left out, and
+/// so are entries that aren't UTF-8, the way a recursive search skips
+/// binary files. Every entry is unpacked into memory.
+pub fn zip_sources<R: Read + Seek>(name: &str, archive: R) -> io::Result<Vec<Box<dyn Source>>> {
+    let mut archive = ZipArchive::new(archive)?;
+    let mut sources: Vec<Box<dyn Source>> = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        if !entry.is_file() {
+            continue;
+        }
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+        // binary entries can't be searched as text, skip them
+        let Ok(text) = String::from_utf8(bytes) else {
+            continue;
+        };
+        let entry_name = format!("{}!{}", name, entry.name()?);
+        sources.push(Box::new(StringSource::new(&entry_name, &text)));
+    }
+    Ok(sources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use std::io::{Cursor, Write};
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    // a.txt and notes/b.txt, a directory and an image
+    fn archive() -> Cursor<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        zip.start_file("a.txt", options).unwrap();
+        zip.write_all(b"I'm nobody! Who are you?\nHow dreary\n")
+            .unwrap();
+        zip.add_directory("notes/", options).unwrap();
+        zip.start_file("notes/b.txt", options).unwrap();
+        zip.write_all(b"Then there's a pair of us\nAre you nobody, too?\n")
+            .unwrap();
+        zip.start_file("nobody.png", options).unwrap();
+        zip.write_all(&[0x89, b'P', b'N', b'G', 0xff, 0x00])
+            .unwrap();
+        let mut archive = zip.finish().unwrap();
+        archive.set_position(0);
+        archive
+    }
+
+    #[test]
+    fn text_entries_become_sources() {
+        let sources = zip_sources("poems.zip", archive()).unwrap();
+        let names: Vec<&str> = sources.iter().map(|source| source.name()).collect();
+        assert_eq!(vec!["poems.zip!a.txt", "poems.zip!notes/b.txt"], names);
+    }
+
+    #[test]
+    fn matches_are_printed_with_the_entry() {
+        let args = ["minigrep", "-n", "--zip", "poems.zip", "nobody"];
+        let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+        assert_eq!(vec!["poems.zip"], config.filenames);
+
+        let sources = zip_sources("poems.zip", archive()).unwrap();
+        let mut out = Vec::new();
+        crate::run_sources(&config, &sources, &mut out).unwrap();
+        assert_eq!(
+            "poems.zip!a.txt:1:I'm nobody! Who are you?\n\
+             poems.zip!notes/b.txt:2:Are you nobody, too?\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+
+    #[test]
+    fn not_a_zip_is_an_error() {
+        assert!(zip_sources("poem.txt", Cursor::new(b"nobody".to_vec())).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+use std::ops::Range;
+
+/// The ANSI colors used in the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Green,
+}
+
+impl Color {
+    fn code(self) -> &'static str {
+        match self {
+            Color::Red => "31",
+            Color::Green => "32",
+        }
+    }
+}
+
+/// Wraps `text` in the ANSI escape codes for `color`, resetting afterwards.
+///
+/// Callers only do this when stdout is a terminal, so redirected output
+/// never contains escape codes.
+pub fn colorize(text: &str, color: Color) -> String {
+    format!("\x1b[{}m{}\x1b[0m", color.code(), text)
+}
+
+/// Colors the parts of `line` covered by `ranges`, which must be sorted,
+/// non-overlapping, and fall on character boundaries.
+pub fn highlight(line: &str, ranges: &[Range<usize>], color: Color) -> String {
+    let mut highlighted = String::with_capacity(line.len());
+    let mut end = 0;
+    for range in ranges {
+        highlighted.push_str(&line[end..range.start]);
+        highlighted.push_str(&colorize(&line[range.clone()], color));
+        end = range.end;
+    }
+    highlighted.push_str(&line[end..]);
+    highlighted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colorize_wraps_the_text() {
+        assert_eq!("\x1b[31mnobody\x1b[0m", colorize("nobody", Color::Red));
+        assert_eq!("\x1b[32m\x1b[0m", colorize("", Color::Green));
+    }
+
+    #[test]
+    fn highlight_only_colors_the_ranges() {
+        assert_eq!(
+            "I'm \x1b[31mnobody\x1b[0m! Are you \x1b[31mnobody\x1b[0m?",
+            highlight("I'm nobody! Are you nobody?", &[4..10, 20..26], Color::Red)
+        );
+        assert_eq!("Who are you?", highlight("Who are you?", &[], Color::Red));
+    }
+}
@@ -0,0 +1,431 @@
+use regex::Regex;
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+
+/// One line of output from a search with context.
+///
+/// Line numbers start at 1.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Line<'a> {
+    Match(usize, &'a str),
+    Context(usize, &'a str),
+    /// Printed as `--` between groups of lines that aren't adjacent, when
+    /// any context was asked for.
+    Separator,
+}
+
+/// Searches `contents` and returns the matching lines together with up to
+/// `before` lines in front of and `after` lines behind each match.
+///
+/// With `max_count`, scanning stops once that many lines have matched, but
+/// the after-context of the last counted match is still returned. Lines in
+/// that trailing context are reported as `Context` even when they would
+/// match, since they are past the limit.
+pub fn search_with_context<'a, F>(
+    contents: &'a str,
+    is_match: F,
+    before: usize,
+    after: usize,
+    max_count: Option<usize>,
+) -> Vec<Line<'a>>
+where
+    F: Fn(&str) -> bool,
+{
+    search_records(contents, b'\n', is_match, before, after, max_count)
+}
+
+/// Like [`search_with_context`], but with the lines split at `terminator`,
+/// e.g. at NUL bytes for `--null-data`. See [`split_lines`].
+pub fn search_records<'a, F>(
+    contents: &'a str,
+    terminator: u8,
+    is_match: F,
+    before: usize,
+    after: usize,
+    max_count: Option<usize>,
+) -> Vec<Line<'a>>
+where
+    F: Fn(&str) -> bool,
+{
+    let lines: Vec<&str> = split_lines(contents, terminator).collect();
+
+    let mut matched = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if max_count.is_some_and(|max| matched.len() >= max) {
+            break;
+        }
+        if is_match(line) {
+            matched.push(i);
+        }
+    }
+
+    with_context(&lines, &matched, before, after)
+}
+
+/// Like [`search_with_context`], but runs `regex` over the whole of
+/// `contents`, so a match can span several lines.
+///
+/// Every line a match touches is reported as a `Match`, the first of them
+/// being the line the match starts on. `max_count` counts regex matches
+/// rather than lines.
+pub fn search_multiline<'a>(
+    contents: &'a str,
+    regex: &Regex,
+    before: usize,
+    after: usize,
+    max_count: Option<usize>,
+) -> Vec<Line<'a>> {
+    let lines: Vec<&str> = contents.lines().collect();
+    if lines.is_empty() {
+        return Vec::new();
+    }
+
+    let starts = line_starts(contents, b'\n');
+    let line_of = |offset: usize| starts.partition_point(|&start| start <= offset) - 1;
+
+    let mut matched = Vec::new();
+    for found in regex
+        .find_iter(contents)
+        .take(max_count.unwrap_or(usize::MAX))
+    {
+        // after a final newline there is no line left for an empty match
+        if found.start() == contents.len() && contents.ends_with('\n') {
+            continue;
+        }
+        let first = line_of(found.start());
+        let last = line_of(found.end().saturating_sub(1).max(found.start()));
+        matched.extend(first..=last);
+    }
+    matched.dedup();
+
+    with_context(&lines, &matched, before, after)
+}
+
+/// Like [`search_with_context`], but reads `reader` one line at a time and
+/// hands each output line to `emit` as soon as it's known.
+///
+/// Only the last `before` lines are kept around, in a ring buffer, so memory
+/// stays bounded by `before + after + 1` lines however large the input is.
+/// Lines are split like `str::lines`, and invalid UTF-8 is an `InvalidData`
+/// error.
+pub fn stream_with_context<R, F, E>(
+    mut reader: R,
+    is_match: F,
+    before: usize,
+    after: usize,
+    max_count: Option<usize>,
+    mut emit: E,
+) -> io::Result<()>
+where
+    R: BufRead,
+    F: Fn(&str) -> bool,
+    E: FnMut(Line<'_>) -> io::Result<()>,
+{
+    let has_context = before > 0 || after > 0;
+    let mut buffered: VecDeque<(usize, String)> = VecDeque::with_capacity(before);
+    let mut after_left = 0;
+    let mut matched = 0;
+    let mut previous = None;
+    let mut line = String::new();
+
+    for number in 1.. {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let text = line.strip_suffix('\n').unwrap_or(&line);
+        let text = text.strip_suffix('\r').unwrap_or(text);
+
+        if max_count.is_some_and(|max| matched >= max) {
+            // past the limit, only the after-context of the last match is left
+            if after_left == 0 {
+                break;
+            }
+            after_left -= 1;
+            emit(Line::Context(number, text))?;
+            previous = Some(number);
+        } else if is_match(text) {
+            let first = buffered.front().map_or(number, |&(n, _)| n);
+            if has_context && previous.is_some_and(|p: usize| p + 1 != first) {
+                emit(Line::Separator)?;
+            }
+            for (n, kept) in buffered.drain(..) {
+                emit(Line::Context(n, &kept))?;
+            }
+            emit(Line::Match(number, text))?;
+            matched += 1;
+            after_left = after;
+            previous = Some(number);
+        } else if after_left > 0 {
+            after_left -= 1;
+            emit(Line::Context(number, text))?;
+            previous = Some(number);
+        } else if before > 0 {
+            // reuse the oldest line's allocation once the buffer is full
+            let mut kept = match buffered.len() {
+                len if len == before => buffered.pop_front().unwrap().1,
+                _ => String::new(),
+            };
+            kept.clear();
+            kept.push_str(text);
+            buffered.push_back((number, kept));
+        }
+    }
+    Ok(())
+}
+
+/// Splits `contents` into lines ending in `terminator`, which has to be an
+/// ASCII byte.
+///
+/// Like `str::lines`, a final terminator doesn't start another, empty line.
+/// With `\n`, this is exactly `str::lines`, so a `\r` before it is dropped
+/// too.
+pub fn split_lines(contents: &str, terminator: u8) -> Box<dyn Iterator<Item = &str> + '_> {
+    if terminator == b'\n' {
+        Box::new(contents.lines())
+    } else {
+        Box::new(contents.split_terminator(char::from(terminator)))
+    }
+}
+
+/// Returns the byte offset where each line of `contents` starts, counting
+/// the terminators of the lines before it.
+pub fn line_starts(contents: &str, terminator: u8) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    for line in contents.split_inclusive(char::from(terminator)) {
+        starts.push(offset);
+        offset += line.len();
+    }
+    starts
+}
+
+// Turns the indices of the matching lines, in order and without duplicates,
+// into output lines with their context.
+fn with_context<'a>(
+    lines: &[&'a str],
+    matched: &[usize],
+    before: usize,
+    after: usize,
+) -> Vec<Line<'a>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Keep {
+        No,
+        Context,
+        Match,
+    }
+
+    let mut keep = vec![Keep::No; lines.len()];
+    for &m in matched {
+        let last = (m + after).min(lines.len() - 1);
+        for kept in &mut keep[m.saturating_sub(before)..=last] {
+            if *kept == Keep::No {
+                *kept = Keep::Context;
+            }
+        }
+    }
+    for &m in matched {
+        keep[m] = Keep::Match;
+    }
+
+    let has_context = before > 0 || after > 0;
+    let mut results = Vec::new();
+    let mut previous = None;
+    for (i, line) in lines.iter().enumerate() {
+        let number = i + 1;
+        let line = match keep[i] {
+            Keep::No => continue,
+            Keep::Context => Line::Context(number, line),
+            Keep::Match => Line::Match(number, line),
+        };
+        if has_context && previous.is_some_and(|p| p + 1 != i) {
+            results.push(Line::Separator);
+        }
+        results.push(line);
+        previous = Some(i);
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENTS: &str = "\
+one
+two match
+three
+four match
+five
+six
+seven
+eight match
+nine";
+
+    fn is_match(line: &str) -> bool {
+        line.contains("match")
+    }
+
+    #[test]
+    fn no_context_returns_only_matches() {
+        assert_eq!(
+            vec![
+                Line::Match(2, "two match"),
+                Line::Match(4, "four match"),
+                Line::Match(8, "eight match"),
+            ],
+            search_with_context(CONTENTS, is_match, 0, 0, None)
+        );
+    }
+
+    #[test]
+    fn overlapping_context_is_merged() {
+        assert_eq!(
+            vec![
+                Line::Context(1, "one"),
+                Line::Match(2, "two match"),
+                Line::Context(3, "three"),
+                Line::Match(4, "four match"),
+                Line::Context(5, "five"),
+                Line::Separator,
+                Line::Context(7, "seven"),
+                Line::Match(8, "eight match"),
+                Line::Context(9, "nine"),
+            ],
+            search_with_context(CONTENTS, is_match, 1, 1, None)
+        );
+    }
+
+    #[test]
+    fn max_count_still_flushes_after_context() {
+        assert_eq!(
+            vec![
+                Line::Match(2, "two match"),
+                Line::Context(3, "three"),
+                Line::Context(4, "four match"),
+            ],
+            search_with_context(CONTENTS, is_match, 0, 2, Some(1))
+        );
+    }
+
+    #[test]
+    fn context_stops_at_the_edges_of_the_file() {
+        assert_eq!(
+            vec![
+                Line::Context(7, "seven"),
+                Line::Match(8, "eight match"),
+                Line::Context(9, "nine")
+            ],
+            search_with_context(CONTENTS, |line| line.contains("eight"), 1, 5, None)
+        );
+        assert!(search_with_context("", is_match, 2, 2, None).is_empty());
+    }
+
+    #[test]
+    fn line_starts_count_line_endings() {
+        assert_eq!(vec![0, 4, 9], line_starts("one\ntwo\r\nthree", b'\n'));
+        assert_eq!(vec![0, 4], line_starts("one\ntwo\n", b'\n'));
+        assert_eq!(vec![0, 8], line_starts("one\ntwo\0three", b'\0'));
+        assert!(line_starts("", b'\n').is_empty());
+    }
+
+    #[test]
+    fn split_lines_at_nul() {
+        let split = |contents| split_lines(contents, b'\0').collect::<Vec<_>>();
+        assert_eq!(vec!["a b", "c\nd"], split("a b\0c\nd\0"));
+        assert_eq!(vec!["a", "", "b"], split("a\0\0b"));
+        assert_eq!(vec![""], split("\0"));
+        assert!(split("").is_empty());
+        assert_eq!(
+            vec!["one", "two"],
+            split_lines("one\r\ntwo\n", b'\n').collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn search_records_keeps_newlines_inside_a_record() {
+        let contents = "./a\nb.txt\0./c.txt\0./d.txt\0";
+        assert_eq!(
+            vec![Line::Match(1, "./a\nb.txt"), Line::Context(2, "./c.txt")],
+            search_records(contents, b'\0', |line| line.contains('\n'), 0, 1, None)
+        );
+    }
+
+    #[test]
+    fn streaming_matches_the_buffered_search() {
+        let streamed = |contents: &str, before, after, max_count| {
+            let mut lines = Vec::new();
+            stream_with_context(
+                contents.as_bytes(),
+                is_match,
+                before,
+                after,
+                max_count,
+                |line| {
+                    lines.push(format!("{:?}", line));
+                    Ok(())
+                },
+            )
+            .unwrap();
+            lines
+        };
+        let buffered = |contents, before, after, max_count| {
+            search_with_context(contents, is_match, before, after, max_count)
+                .iter()
+                .map(|line| format!("{:?}", line))
+                .collect::<Vec<_>>()
+        };
+
+        let windows_lines = CONTENTS.replace('\n', "\r\n") + "\r\n";
+        for contents in [CONTENTS, windows_lines.as_str(), "", "match\n\nmatch"] {
+            for (before, after) in [(0, 0), (1, 1), (2, 0), (0, 3), (5, 5)] {
+                for max_count in [None, Some(1), Some(2)] {
+                    assert_eq!(
+                        buffered(contents, before, after, max_count),
+                        streamed(contents, before, after, max_count),
+                        "{:?} -B {} -A {} -m {:?}",
+                        contents,
+                        before,
+                        after,
+                        max_count
+                    );
+                }
+            }
+        }
+    }
+
+    fn multiline(pattern: &str) -> Regex {
+        crate::patterns::build_regex(&[pattern.to_string()], true, true).unwrap()
+    }
+
+    #[test]
+    fn multiline_match_spans_lines() {
+        let contents = "one\nfoo\nbar\nfour\nfoo\nbaz\n";
+        assert_eq!(
+            vec![Line::Match(2, "foo"), Line::Match(3, "bar")],
+            search_multiline(contents, &multiline("foo\nbar"), 0, 0, None)
+        );
+        assert_eq!(
+            vec![
+                Line::Context(1, "one"),
+                Line::Match(2, "foo"),
+                Line::Match(3, "bar"),
+                Line::Context(4, "four"),
+            ],
+            search_multiline(contents, &multiline("foo.bar"), 1, 1, None)
+        );
+    }
+
+    #[test]
+    fn multiline_anchors_match_every_line() {
+        let contents = "one\nfoo\nbar\nfoo";
+        assert_eq!(
+            vec![Line::Match(2, "foo"), Line::Match(4, "foo")],
+            search_multiline(contents, &multiline("^foo$"), 0, 0, None)
+        );
+        assert_eq!(
+            vec![Line::Match(2, "foo")],
+            search_multiline(contents, &multiline("^foo$"), 0, 0, Some(1))
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use std::io;
+
+/// The text encoding of a file, as told by its byte order mark.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Encoding {
+    /// No byte order mark; read as plain UTF-8.
+    Utf8,
+    /// UTF-8 starting with `EF BB BF`.
+    Utf8Bom,
+    /// UTF-16, little endian, starting with `FF FE`.
+    Utf16Le,
+    /// UTF-16, big endian, starting with `FE FF`.
+    Utf16Be,
+}
+
+impl Encoding {
+    /// How many bytes the byte order mark takes up.
+    pub fn bom_len(self) -> usize {
+        match self {
+            Encoding::Utf8 => 0,
+            Encoding::Utf8Bom => 3,
+            Encoding::Utf16Le | Encoding::Utf16Be => 2,
+        }
+    }
+}
+
+/// Looks for a byte order mark at the start of a file.
+pub fn detect_encoding(prefix: &[u8]) -> Encoding {
+    match prefix {
+        [0xEF, 0xBB, 0xBF, ..] => Encoding::Utf8Bom,
+        [0xFF, 0xFE, ..] => Encoding::Utf16Le,
+        [0xFE, 0xFF, ..] => Encoding::Utf16Be,
+        _ => Encoding::Utf8,
+    }
+}
+
+/// Decodes a whole file according to its byte order mark, leaving the mark
+/// itself out.
+///
+/// Fails with `InvalidData`, like `fs::read_to_string`, when the bytes
+/// aren't valid in the detected encoding.
+pub fn decode(bytes: &[u8]) -> io::Result<String> {
+    let encoding = detect_encoding(bytes);
+    let bytes = &bytes[encoding.bom_len()..];
+
+    let decoded = match encoding {
+        Encoding::Utf8 | Encoding::Utf8Bom => String::from_utf8(bytes.to_vec()).ok(),
+        Encoding::Utf16Le => decode_utf16(bytes, u16::from_le_bytes),
+        Encoding::Utf16Be => decode_utf16(bytes, u16::from_be_bytes),
+    };
+
+    decoded.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("file is not valid {:?}", encoding),
+        )
+    })
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Option<String> {
+    if !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| from_bytes([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16(&units).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        let mut bytes = if big_endian {
+            vec![0xFE, 0xFF]
+        } else {
+            vec![0xFF, 0xFE]
+        };
+        for unit in text.encode_utf16() {
+            if big_endian {
+                bytes.extend(unit.to_be_bytes());
+            } else {
+                bytes.extend(unit.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn detects_each_bom() {
+        assert_eq!(Encoding::Utf8Bom, detect_encoding(b"\xEF\xBB\xBFnobody"));
+        assert_eq!(Encoding::Utf16Le, detect_encoding(b"\xFF\xFEn\0"));
+        assert_eq!(Encoding::Utf16Be, detect_encoding(b"\xFE\xFF\0n"));
+        assert_eq!(Encoding::Utf8, detect_encoding(b"nobody"));
+        assert_eq!(Encoding::Utf8, detect_encoding(b"\xEF\xBB"));
+        assert_eq!(Encoding::Utf8, detect_encoding(b""));
+    }
+
+    #[test]
+    fn utf8_bom_is_stripped() {
+        assert_eq!(
+            "I'm nobody!\n",
+            decode(b"\xEF\xBB\xBFI'm nobody!\n").unwrap()
+        );
+        assert_eq!("plain", decode(b"plain").unwrap());
+    }
+
+    #[test]
+    fn utf16_is_decoded() {
+        let text = "I'm nobody! Who are you?\nAre you nobody, too? ñ\n";
+        assert_eq!(text, decode(&utf16(text, false)).unwrap());
+        assert_eq!(text, decode(&utf16(text, true)).unwrap());
+    }
+
+    #[test]
+    fn invalid_data_is_an_error() {
+        assert_eq!(
+            io::ErrorKind::InvalidData,
+            decode(b"\xFF\xFEn").unwrap_err().kind()
+        );
+        assert_eq!(
+            io::ErrorKind::InvalidData,
+            decode(b"\xEF\xBB\xBF\xFF").unwrap_err().kind()
+        );
+    }
+}
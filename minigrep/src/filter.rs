@@ -0,0 +1,130 @@
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use std::path::Path;
+
+/// Decides which files a recursive search looks at, for `--include` and
+/// `--exclude`.
+///
+/// The globs are matched against the path of a file relative to the
+/// searched directory. `*` and `?` never match a `/`, so `*.rs` only
+/// matches files at the top of the directory, while `**` matches any number
+/// of directories: `**/*.rs` matches Rust files at every level, and
+/// `src/**/*.rs` every one below `src`.
+pub struct PathFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl PathFilter {
+    /// With no `include` globs every file is included, unless an `exclude`
+    /// glob matches it.
+    pub fn new(include: &[String], exclude: &[String]) -> Result<PathFilter, globset::Error> {
+        let include = if include.is_empty() {
+            None
+        } else {
+            Some(glob_set(include)?)
+        };
+        Ok(PathFilter {
+            include,
+            exclude: glob_set(exclude)?,
+        })
+    }
+
+    /// Whether the file at `relative` should be searched.
+    pub fn is_included(&self, relative: &Path) -> bool {
+        let included = match &self.include {
+            Some(include) => include.is_match(relative),
+            None => true,
+        };
+        included && !self.exclude.is_match(relative)
+    }
+}
+
+/// Parses a file size for `--max-filesize`: a number of bytes, optionally
+/// followed by `K`, `M` or `G` for KiB, MiB and GiB, so `10M` is
+/// 10,485,760 bytes. Sizes too big for a `u64` are `None`, like anything
+/// else that isn't a size.
+pub fn parse_size(size: &str) -> Option<u64> {
+    let (number, unit) = match size.char_indices().last()? {
+        (i, 'K' | 'k') => (&size[..i], 1 << 10),
+        (i, 'M' | 'm') => (&size[..i], 1 << 20),
+        (i, 'G' | 'g') => (&size[..i], 1 << 30),
+        _ => (size, 1),
+    };
+    // parse() would also take a leading `+`
+    if !number.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    number.parse::<u64>().ok()?.checked_mul(unit)
+}
+
+fn glob_set(globs: &[String]) -> Result<GlobSet, globset::Error> {
+    let mut set = GlobSetBuilder::new();
+    for glob in globs {
+        set.add(build_glob(glob)?);
+    }
+    set.build()
+}
+
+fn build_glob(glob: &str) -> Result<Glob, globset::Error> {
+    GlobBuilder::new(glob).literal_separator(true).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path_filter(include: &[&str], exclude: &[&str]) -> PathFilter {
+        let strings = |globs: &[&str]| globs.iter().map(|g| g.to_string()).collect::<Vec<_>>();
+        PathFilter::new(&strings(include), &strings(exclude)).unwrap()
+    }
+
+    #[test]
+    fn star_stays_in_one_directory() {
+        let filter = path_filter(&["*.rs"], &[]);
+        assert!(filter.is_included(Path::new("main.rs")));
+        assert!(!filter.is_included(Path::new("src/main.rs")));
+        assert!(!filter.is_included(Path::new("notes.txt")));
+    }
+
+    #[test]
+    fn double_star_crosses_directories() {
+        let filter = path_filter(&["**/*.rs"], &[]);
+        assert!(filter.is_included(Path::new("main.rs")));
+        assert!(filter.is_included(Path::new("src/main.rs")));
+        assert!(filter.is_included(Path::new("src/bin/deep/tool.rs")));
+        assert!(!filter.is_included(Path::new("src/notes.txt")));
+
+        let filter = path_filter(&["src/**/*.rs"], &[]);
+        assert!(filter.is_included(Path::new("src/main.rs")));
+        assert!(filter.is_included(Path::new("src/bin/tool.rs")));
+        assert!(!filter.is_included(Path::new("benches/patterns.rs")));
+    }
+
+    #[test]
+    fn exclude_wins_over_include() {
+        let filter = path_filter(&["**/*.rs"], &["target/**"]);
+        assert!(filter.is_included(Path::new("src/lib.rs")));
+        assert!(!filter.is_included(Path::new("target/debug/build.rs")));
+
+        let filter = path_filter(&[], &["*.lock"]);
+        assert!(filter.is_included(Path::new("src/lib.rs")));
+        assert!(!filter.is_included(Path::new("Cargo.lock")));
+    }
+
+    #[test]
+    fn sizes_take_a_unit() {
+        assert_eq!(Some(500), parse_size("500"));
+        assert_eq!(Some(2048), parse_size("2K"));
+        assert_eq!(Some(10 * 1024 * 1024), parse_size("10M"));
+        assert_eq!(Some(3 << 30), parse_size("3g"));
+        assert_eq!(Some(0), parse_size("0K"));
+        for invalid in ["", "M", "10MB", "1.5M", "-1K", "+1K", "99999999999G"] {
+            assert_eq!(None, parse_size(invalid), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn invalid_globs_are_errors() {
+        assert!(PathFilter::new(&["src/[".to_string()], &[]).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// Reads the lines appended to a file since it was last checked, like
+/// `tail -f`.
+pub struct Follower {
+    path: PathBuf,
+    file: File,
+    position: u64,
+    // the end of the file when it doesn't end with a newline yet
+    partial: Vec<u8>,
+}
+
+impl Follower {
+    /// Opens `path` and starts at its current end, so only lines written
+    /// from now on are returned.
+    pub fn new(path: &Path) -> io::Result<Follower> {
+        let mut file = File::open(path)?;
+        let position = file.seek(SeekFrom::End(0))?;
+        Ok(Follower {
+            path: path.to_path_buf(),
+            file,
+            position,
+            partial: Vec::new(),
+        })
+    }
+
+    /// Returns every line completed since the last call.
+    ///
+    /// If the file got smaller, it was truncated or rotated, so it is opened
+    /// again and read from the start.
+    pub fn read_lines(&mut self) -> io::Result<Vec<String>> {
+        if fs::metadata(&self.path)?.len() < self.position {
+            self.file = File::open(&self.path)?;
+            self.position = 0;
+            self.partial.clear();
+        }
+
+        let mut new_data = Vec::new();
+        self.file.seek(SeekFrom::Start(self.position))?;
+        self.position += self.file.read_to_end(&mut new_data)? as u64;
+        self.partial.extend_from_slice(&new_data);
+
+        let complete = match self.partial.iter().rposition(|&b| b == b'\n') {
+            Some(last_newline) => self.partial.drain(..=last_newline).collect::<Vec<u8>>(),
+            None => return Ok(Vec::new()),
+        };
+
+        Ok(String::from_utf8_lossy(&complete)
+            .lines()
+            .map(String::from)
+            .collect())
+    }
+}
+
+/// Follows `path` forever, calling `on_line` with every new line that
+/// matches, and checking for new data every `interval`.
+pub fn follow<F, M>(path: &Path, interval: Duration, is_match: M, mut on_line: F) -> io::Result<()>
+where
+    M: Fn(&str) -> bool,
+    F: FnMut(&str) -> io::Result<()>,
+{
+    let mut follower = Follower::new(path)?;
+    loop {
+        for line in follower.read_lines()? {
+            if is_match(&line) {
+                on_line(&line)?;
+            }
+        }
+        thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    fn append(path: &Path, text: &str) {
+        let mut file = OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn only_reads_lines_written_after_opening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "old nobody\n").unwrap();
+
+        let mut follower = Follower::new(&path).unwrap();
+        assert!(follower.read_lines().unwrap().is_empty());
+
+        append(&path, "I'm nobody!\nWho are you?\n");
+        assert_eq!(
+            vec!["I'm nobody!", "Who are you?"],
+            follower.read_lines().unwrap()
+        );
+
+        append(&path, "Are you nobody, too?\n");
+        assert_eq!(vec!["Are you nobody, too?"], follower.read_lines().unwrap());
+    }
+
+    #[test]
+    fn waits_for_a_line_to_be_completed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "").unwrap();
+
+        let mut follower = Follower::new(&path).unwrap();
+        append(&path, "Are you no");
+        assert!(follower.read_lines().unwrap().is_empty());
+
+        append(&path, "body, too?\n");
+        assert_eq!(vec!["Are you nobody, too?"], follower.read_lines().unwrap());
+    }
+
+    #[test]
+    fn starts_over_after_truncation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "a long line that will be gone\n").unwrap();
+
+        let mut follower = Follower::new(&path).unwrap();
+        fs::write(&path, "nobody\n").unwrap();
+        assert_eq!(vec!["nobody"], follower.read_lines().unwrap());
+    }
+}
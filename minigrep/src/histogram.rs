@@ -0,0 +1,161 @@
+use std::path::{Path, PathBuf};
+
+/// The matches in each file, drawn as a bar chart by `--histogram`.
+///
+/// Files are sorted by their number of matches, most first, and files with
+/// as many matches by path, so the chart is the same with `--threads`.
+/// Files without a match are left out.
+pub struct Histogram<'a> {
+    bars: Vec<(&'a Path, u64)>,
+}
+
+impl<'a> Histogram<'a> {
+    pub fn new(files: &'a [(PathBuf, u64)]) -> Histogram<'a> {
+        let mut bars: Vec<_> = files
+            .iter()
+            .filter(|(_, matches)| *matches > 0)
+            .map(|(path, matches)| (path.as_path(), *matches))
+            .collect();
+        bars.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        Histogram { bars }
+    }
+
+    /// The files and their matches, in the order they are drawn.
+    pub fn bars(&self) -> &[(&'a Path, u64)] {
+        &self.bars
+    }
+
+    /// One line per file, like `src/a.rs ████ 12`, fitting in `width`
+    /// columns.
+    ///
+    /// The file with the most matches gets the longest bar and the others
+    /// are scaled to it, but every file gets at least one block, however
+    /// narrow `width` is.
+    pub fn render(&self, width: usize) -> String {
+        let names: Vec<_> = self
+            .bars
+            .iter()
+            .map(|(path, _)| path.display().to_string())
+            .collect();
+        let name_width = names.iter().map(|n| n.chars().count()).max().unwrap_or(0);
+        let most = self.bars.first().map_or(0, |&(_, matches)| matches);
+        let count_width = most.to_string().len();
+        let bar_width = width.saturating_sub(name_width + count_width + 2).max(1) as u64;
+
+        let mut chart = String::new();
+        for (name, &(_, matches)) in names.iter().zip(&self.bars) {
+            let blocks = (matches * bar_width).div_ceil(most) as usize;
+            chart.push_str(&format!(
+                "{:<name_width$} {} {}\n",
+                name,
+                "█".repeat(blocks),
+                matches
+            ));
+        }
+        chart
+    }
+}
+
+/// The width to draw the chart in: the width of the terminal stdout or
+/// stderr is shown on, asked with an ioctl or, off Unix, with crossterm
+/// when the `interactive` feature is on. Without a terminal it is
+/// `$COLUMNS`, which shells don't always pass on, or else 80.
+pub fn terminal_width() -> usize {
+    terminal_columns()
+        .or_else(|| {
+            std::env::var("COLUMNS")
+                .ok()
+                .and_then(|columns| columns.parse().ok())
+        })
+        .filter(|&columns| columns > 0)
+        .unwrap_or(80)
+}
+
+// stdout first, where the chart goes, then stderr, which is still the
+// terminal when the chart is piped into a pager
+#[cfg(unix)]
+fn terminal_columns() -> Option<usize> {
+    [libc::STDOUT_FILENO, libc::STDERR_FILENO]
+        .into_iter()
+        .find_map(|fd| {
+            let mut size = libc::winsize {
+                ws_row: 0,
+                ws_col: 0,
+                ws_xpixel: 0,
+                ws_ypixel: 0,
+            };
+            // SAFETY: TIOCGWINSZ only writes a `winsize` to the pointer
+            let found = unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) } == 0;
+            (found && size.ws_col > 0).then_some(usize::from(size.ws_col))
+        })
+}
+
+// crossterm asks the console, which is there even when both outputs are
+// redirected, so it is only asked when one of them isn't
+#[cfg(all(not(unix), feature = "interactive"))]
+fn terminal_columns() -> Option<usize> {
+    use std::io::IsTerminal;
+
+    if !(std::io::stdout().is_terminal() || std::io::stderr().is_terminal()) {
+        return None;
+    }
+    crossterm::terminal::size()
+        .ok()
+        .map(|(columns, _)| usize::from(columns))
+}
+
+#[cfg(all(not(unix), not(feature = "interactive")))]
+fn terminal_columns() -> Option<usize> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(counts: &[(&str, u64)]) -> Vec<(PathBuf, u64)> {
+        counts
+            .iter()
+            .map(|&(path, matches)| (PathBuf::from(path), matches))
+            .collect()
+    }
+
+    #[test]
+    fn most_matches_come_first() {
+        let files = files(&[
+            ("b.rs", 3),
+            ("a.rs", 1),
+            ("c.rs", 0),
+            ("d.rs", 12),
+            ("a/e.rs", 3),
+        ]);
+        let histogram = Histogram::new(&files);
+        assert_eq!(
+            vec![
+                (Path::new("d.rs"), 12),
+                (Path::new("a/e.rs"), 3),
+                (Path::new("b.rs"), 3),
+                (Path::new("a.rs"), 1)
+            ],
+            histogram.bars()
+        );
+    }
+
+    #[test]
+    fn bars_are_scaled_to_the_width() {
+        let files = files(&[("a.rs", 1), ("long/b.rs", 10), ("c.rs", 5)]);
+        let histogram = Histogram::new(&files);
+        // 9 columns for the names, 2 for the counts and 2 spaces leave 10
+        assert_eq!(
+            "long/b.rs ██████████ 10\n\
+             c.rs      █████ 5\n\
+             a.rs      █ 1\n",
+            histogram.render(23)
+        );
+        assert_eq!(
+            "long/b.rs █ 10\nc.rs      █ 5\na.rs      █ 1\n",
+            histogram.render(0)
+        );
+        assert_eq!("", Histogram::new(&[]).render(80));
+    }
+}
@@ -0,0 +1,230 @@
+use crate::matcher;
+use crate::Config;
+use std::io;
+
+/// The lines of one file, narrowed down to the ones matching a query that
+/// changes with every keystroke, for `--interactive`.
+///
+/// Matching goes through the same [`matcher::Matcher`] as a normal search,
+/// so `--regex`, `-i` and `-w` work the same way here.
+pub struct Refiner<'a> {
+    config: Config,
+    lines: Vec<&'a str>,
+    query: String,
+    // indices of the lines matching `query`
+    matches: Vec<usize>,
+}
+
+impl<'a> Refiner<'a> {
+    /// Starts with the empty query, which every line matches.
+    pub fn new(config: &Config, contents: &'a str) -> Refiner<'a> {
+        let lines: Vec<&str> = contents.lines().collect();
+        Refiner {
+            config: config.clone(),
+            matches: (0..lines.len()).collect(),
+            lines,
+            query: String::new(),
+        }
+    }
+
+    /// Changes the query and finds the lines matching it.
+    ///
+    /// When a literal query only got longer, just the lines that matched
+    /// before are searched again. A regex that doesn't compile, as happens
+    /// halfway through typing one, is an error and keeps the old matches.
+    pub fn set_query(&mut self, query: &str) -> io::Result<()> {
+        let mut config = self.config.clone();
+        config.query = query.to_string();
+        config.patterns = vec![query.to_string()];
+        let matcher = matcher::from_config(&config)?;
+
+        // a longer literal can only match fewer lines, but a longer whole
+        // word or regex can match others
+        let narrows = !(config.regex || config.word || config.invert);
+        let candidates = if narrows && query.starts_with(&self.query) {
+            std::mem::take(&mut self.matches)
+        } else {
+            (0..self.lines.len()).collect()
+        };
+        self.matches = candidates
+            .into_iter()
+            .filter(|&i| matcher.is_match(self.lines[i]) != config.invert)
+            .collect();
+        self.query = query.to_string();
+        Ok(())
+    }
+
+    /// The matching lines with their line numbers, starting at 1.
+    pub fn matching_lines(&self) -> impl Iterator<Item = (usize, &'a str)> + '_ {
+        self.matches.iter().map(|&i| (i + 1, self.lines[i]))
+    }
+}
+
+/// Reads `config.filename` once, then filters it live while a query is
+/// typed into the terminal.
+///
+/// The terminal shows the query and the lines matching it, on stderr. Enter
+/// writes the matching lines to `out` and returns how many there were; Esc
+/// or Ctrl-C leave without writing anything.
+#[cfg(feature = "interactive")]
+pub fn run(config: &Config, out: &mut dyn io::Write) -> Result<usize, Box<dyn std::error::Error>> {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+
+    let contents = std::fs::read_to_string(&config.filename)?;
+    let mut refiner = Refiner::new(config, &contents);
+    let mut query = config.query.clone();
+    refiner.set_query(&query)?;
+
+    let mut screen = io::stderr();
+    let accepted = {
+        let _raw = RawMode::enter(&mut screen)?;
+        let mut invalid = false;
+        loop {
+            draw(&mut screen, &query, invalid, &refiner)?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Enter => break true,
+                KeyCode::Esc => break false,
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break false,
+                KeyCode::Char(c) => query.push(c),
+                KeyCode::Backspace => {
+                    query.pop();
+                }
+                _ => continue,
+            }
+            invalid = refiner.set_query(&query).is_err();
+        }
+    };
+
+    if !accepted {
+        return Ok(0);
+    }
+    let mut printed = 0;
+    for (_, line) in refiner.matching_lines() {
+        writeln!(out, "{}", line)?;
+        printed += 1;
+    }
+    Ok(printed)
+}
+
+// Raw input on an alternate screen, until this is dropped, so that the
+// terminal is restored even when reading a key fails.
+#[cfg(feature = "interactive")]
+struct RawMode;
+
+#[cfg(feature = "interactive")]
+impl RawMode {
+    fn enter(screen: &mut impl io::Write) -> io::Result<RawMode> {
+        crossterm::terminal::enable_raw_mode()?;
+        crossterm::execute!(screen, crossterm::terminal::EnterAlternateScreen)?;
+        Ok(RawMode)
+    }
+}
+
+#[cfg(feature = "interactive")]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = crossterm::execute!(io::stderr(), crossterm::terminal::LeaveAlternateScreen);
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
+
+// The query on the first row, and as many matching lines as fit below it.
+#[cfg(feature = "interactive")]
+fn draw(
+    screen: &mut impl io::Write,
+    query: &str,
+    invalid: bool,
+    refiner: &Refiner,
+) -> io::Result<()> {
+    use crossterm::cursor::MoveTo;
+    use crossterm::queue;
+    use crossterm::style::Print;
+    use crossterm::terminal::{self, Clear, ClearType};
+
+    let (width, height) = terminal::size()?;
+    let prompt = format!("> {}", query);
+    queue!(screen, Clear(ClearType::All), MoveTo(0, 0), Print(&prompt))?;
+    if invalid {
+        queue!(screen, Print("  (invalid pattern)"))?;
+    }
+    for (row, (number, line)) in (1..height).zip(refiner.matching_lines()) {
+        let line = format!("{}:{}", number, line);
+        let line = crate::truncate(&line, Some(usize::from(width).saturating_sub(1)));
+        queue!(screen, MoveTo(0, row), Print(line))?;
+    }
+    let column = prompt.chars().count().min(usize::from(u16::MAX)) as u16;
+    queue!(screen, MoveTo(column, 0))?;
+    screen.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENTS: &str = "\
+I'm nobody! Who are you?
+Are you nobody, too?
+Then there's a pair of us - don't tell!
+They'd banish us, you know.
+How dreary to be somebody!";
+
+    fn config(args: &[&str]) -> Config {
+        let args = ["minigrep"].iter().chain(args).map(|arg| arg.to_string());
+        Config::new(args).unwrap()
+    }
+
+    fn numbers(refiner: &Refiner) -> Vec<usize> {
+        refiner.matching_lines().map(|(number, _)| number).collect()
+    }
+
+    #[test]
+    fn each_keystroke_refines_the_matches() {
+        let mut refiner = Refiner::new(&config(&["x", "poem.txt"]), CONTENTS);
+        assert_eq!(vec![1, 2, 3, 4, 5], numbers(&refiner));
+
+        for (query, expected) in [
+            ("b", vec![1, 2, 4, 5]),
+            ("bo", vec![1, 2, 5]),
+            ("bod", vec![1, 2, 5]),
+            ("body", vec![1, 2, 5]),
+            ("body,", vec![2]),
+            ("body", vec![1, 2, 5]),
+            ("", vec![1, 2, 3, 4, 5]),
+            ("us", vec![3, 4]),
+        ] {
+            refiner.set_query(query).unwrap();
+            assert_eq!(expected, numbers(&refiner), "{:?}", query);
+        }
+        assert_eq!(
+            vec![
+                (3, "Then there's a pair of us - don't tell!"),
+                (4, "They'd banish us, you know.")
+            ],
+            refiner.matching_lines().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn longer_words_can_match_other_lines() {
+        let mut refiner = Refiner::new(&config(&["-w", "x", "poem.txt"]), CONTENTS);
+        refiner.set_query("us").unwrap();
+        assert_eq!(vec![3, 4], numbers(&refiner));
+        refiner.set_query("you").unwrap();
+        assert_eq!(vec![1, 2, 4], numbers(&refiner));
+    }
+
+    #[test]
+    fn unfinished_regex_keeps_the_last_matches() {
+        let mut refiner = Refiner::new(&config(&["--regex", "x", "poem.txt"]), CONTENTS);
+        refiner.set_query("^(Are").unwrap_err();
+        assert_eq!(vec![1, 2, 3, 4, 5], numbers(&refiner));
+        refiner.set_query("^(Are|How)").unwrap();
+        assert_eq!(vec![2, 5], numbers(&refiner));
+    }
+}
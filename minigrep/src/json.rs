@@ -0,0 +1,84 @@
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::Path;
+
+/// Writes one matching line for `--json` as a JSON object on a line of its
+/// own, so the output can be read one object at a time (JSON Lines):
+///
+/// ```text
+/// {"path":"poem.txt","line_number":2,"text":"Are you nobody, too?","ranges":[[8,14]]}
+/// ```
+///
+/// `ranges` are the matches as byte offsets into `text`, start inclusive
+/// and end exclusive, the way [`crate::match_ranges`] returns them. They
+/// always fall on character boundaries, also when `text` isn't ASCII.
+pub fn write_match(
+    out: &mut dyn Write,
+    path: &Path,
+    number: usize,
+    text: &str,
+    ranges: &[Range<usize>],
+) -> io::Result<()> {
+    let ranges: Vec<String> = ranges
+        .iter()
+        .map(|range| format!("[{},{}]", range.start, range.end))
+        .collect();
+    writeln!(
+        out,
+        r#"{{"path":"{}","line_number":{},"text":"{}","ranges":[{}]}}"#,
+        escape(&path.to_string_lossy()),
+        number,
+        escape(text),
+        ranges.join(",")
+    )
+}
+
+// Escapes `s` for a JSON string: quotes, backslashes and control characters.
+// Everything else, including non-ASCII, is valid in a JSON string as is.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                escaped.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn special_characters_are_escaped() {
+        assert_eq!(r#"say \"hi\"\\n"#, escape("say \"hi\"\\n"));
+        assert_eq!(r#"a\tb\u0000\u001b"#, escape("a\tb\0\u{1b}"));
+        assert_eq!("école 😻", escape("école 😻"));
+    }
+
+    #[test]
+    fn one_object_per_line() {
+        let mut out = Vec::new();
+        write_match(
+            &mut out,
+            Path::new("poem.txt"),
+            2,
+            "you, \"you\"",
+            &[0..3, 6..9],
+        )
+        .unwrap();
+        assert_eq!(
+            "{\"path\":\"poem.txt\",\"line_number\":2,\"text\":\"you, \\\"you\\\"\",\
+             \"ranges\":[[0,3],[6,9]]}\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+}
@@ -1,53 +1,1886 @@
+//! Search files for lines containing a query, like a small `grep`.
+//!
+//! The `minigrep` binary is a thin wrapper around this library: it builds a
+//! [`Config`] from the command line and hands it to [`run`] together with
+//! stdout. Other programs can do the same with any writer, or call
+//! [`search`] and [`search_case_insensitive`] directly on text they already
+//! have.
+//!
+//! ```
+//! let contents = "Rust:\nsafe, fast, productive.\nTrust me.";
+//! assert_eq!(vec!["safe, fast, productive."], minigrep::search("duct", contents));
+//! ```
+
+use std::collections::HashMap;
 use std::error::Error;
-use std::fs;
+use std::fmt;
+use std::fs::{self, File};
 use std::env;
+use std::io::{self, BufRead, BufWriter, LineWriter, Read, Write};
+use std::mem;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub mod align;
+pub mod archive;
+pub mod color;
+pub mod context;
+pub mod encoding;
+pub mod filter;
+pub mod follow;
+pub mod histogram;
+pub mod interactive;
+pub mod json;
+pub mod limit;
+pub mod matcher;
+pub mod mmap;
+pub mod patterns;
+pub mod progress;
+pub mod replace;
+pub mod source;
+pub mod stats;
+pub mod walk;
 
+use align::Field;
+use color::Color;
+use filter::PathFilter;
+use histogram::Histogram;
+use context::Line;
+use limit::LineLimit;
+use matcher::Matcher;
+use mmap::Contents;
+use progress::Progress;
+use source::Source;
+use regex::Regex;
+use replace::{Pattern, ReplaceCount};
+use stats::{ScanStats, Summary};
+
+/// What to search for, where, and how to print the results.
+///
+/// Usually built from command-line arguments with [`Config::new`].
+///
+/// With the `serde` feature it can also be read from a TOML file, see
+/// [`Config::from_toml`]. Every field is optional there.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 pub struct Config {
     pub query: String,
+    /// Every literal pattern to look for; `query` is the first of them.
+    pub patterns: Vec<String>,
     pub filename: String,
+    /// Every file (or directory, with `-r`) to search; `filename` is the
+    /// first of them.
+    pub filenames: Vec<String>,
     pub case_sensitive: bool,
+    /// Ignore case unless a pattern has an uppercase letter. When set,
+    /// `case_sensitive` is worked out from the patterns instead of the
+    /// `CASE_INSENSITIVE` environment variable.
+    pub smart_case: bool,
+    pub replace: Option<String>,
+    pub in_place: bool,
+    pub backup: Option<String>,
+    /// Write the replaced files under this directory instead of over the
+    /// originals, in the same place relative to the searched directory.
+    pub output_dir: Option<String>,
+    pub dry_run: bool,
+    /// Search the text files inside this ZIP archive, see
+    /// [`archive::zip_sources`]. It is the only file searched.
+    pub zip: Option<String>,
+    pub recursive: bool,
+    /// Follow symlinks while searching recursively, like `grep -R`.
+    pub follow_symlinks: bool,
+    /// Globs for the files a recursive search looks at, and the ones it
+    /// skips, matched against paths relative to the searched directory. See
+    /// [`filter::PathFilter`].
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Skip files bigger than this many bytes in a recursive search.
+    pub max_filesize: Option<u64>,
+    /// Say on stderr which files a recursive search skipped.
+    pub verbose: bool,
+    pub progress: bool,
+    pub before_context: usize,
+    pub after_context: usize,
+    pub max_count: Option<usize>,
+    pub follow: bool,
+    /// Filter one file live while the query is typed, see
+    /// [`interactive::run`]. `query` is where the typing starts. Needs the
+    /// `interactive` feature.
+    pub interactive: bool,
+    /// The query isn't on the command line, every positional argument is a
+    /// file. It is read from stdin with [`Config::read_query`] instead,
+    /// which spares quoting patterns the shell would get in the way of.
+    pub query_from_stdin: bool,
+    pub stats: bool,
+    /// Print only the first matching line of each file, with its line and
+    /// column, and stop reading the file there.
+    pub first_only: bool,
+    /// Print the matching lines of each file sorted by their text instead
+    /// of in file order. This needs every match of a file before the first
+    /// one is printed, so unlike the normal search it can't stream, and it
+    /// doesn't work with `-f`.
+    pub sort_matches: Option<SortOrder>,
+    /// Print how many replacements were made to stderr at the end.
+    pub replace_count: bool,
+    /// Print how many lines matched in each file instead of the lines.
+    pub count: bool,
+    /// With `count`, print how many bytes the counted lines take up, line
+    /// endings included, instead of how many lines there are, like `wc -c`.
+    pub count_bytes: bool,
+    /// Print the lines that don't match instead, like `grep -v`. With
+    /// `count`, those are the lines that are counted.
+    pub invert: bool,
+    /// Only match whole words, like `grep -w`.
+    pub word: bool,
+    /// Print each matching line as `line:occurrences:text`, where
+    /// `occurrences` is how many times the patterns appear on the line.
+    pub show_occurrences: bool,
+    /// Stop once this many lines have been printed, counting every file,
+    /// unlike `max_count`.
+    pub max_output: Option<usize>,
+    /// Cut printed lines down to this many characters, ending them in `…`.
+    /// Only the printing is affected: matches past the cut still count.
+    pub max_columns: Option<usize>,
+    /// Print matching lines without the whitespace around them. Matching,
+    /// `--first` columns and `-b` offsets still use the whole line.
+    pub trim: bool,
+    /// Put the byte offset where each line starts in front of it, like
+    /// `grep -b`.
+    pub byte_offset: bool,
+    /// Put the line number in front of every printed line, like `grep -n`:
+    /// `13:` for a matching line and `12-` for a context line around it.
+    pub context_line_numbers: bool,
+    /// Pad the file names and numbers in front of the lines so that they
+    /// line up. The whole output is held back until the search is done.
+    pub align: bool,
+    /// What goes between the file name, the numbers and the text of a
+    /// matching line, `:` by default. Context lines always use `-`.
+    pub field_separator: String,
+    /// Print the file name once, on a line of its own, above the lines
+    /// found in it instead of in front of each of them.
+    pub heading: bool,
+    /// Print each matching line as a JSON object with its file, line number
+    /// and the byte ranges of the matches, see [`json::write_match`].
+    pub json: bool,
+    /// What goes between the lines of two files with `heading` or context,
+    /// an empty line by default.
+    pub group_separator: Option<String>,
+    /// Print the name of every file without a single matching line, like
+    /// `grep -L`, instead of the lines.
+    pub files_without_match: bool,
+    /// Print how many files matched to stderr at the end.
+    pub summary: bool,
+    /// Print the matching lines as usual, then `Total: N matches` to stderr.
+    pub with_count: bool,
+    /// Instead of the lines, print a bar chart of how many matches each
+    /// file has, most first, scaled to the width of the terminal.
+    pub histogram: bool,
+    /// Treat the patterns as regular expressions instead of literal text.
+    pub regex: bool,
+    /// Run the regex over the whole file, so a match can span lines.
+    pub multiline: bool,
+    /// Decode files by their byte order mark: strip a UTF-8 BOM and read
+    /// UTF-16 files as text.
+    pub encoding_detect: bool,
+    /// Map files into memory instead of reading them, for very large files.
+    pub mmap: bool,
+    /// What to do when a file can't be read.
+    pub error_mode: ErrorMode,
+    /// Flush stdout after every line, even when it isn't a terminal.
+    pub line_buffered: bool,
+    /// Color the matches and the `--dry-run` diff. The binary turns this on
+    /// when stdout is a terminal.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub color: bool,
+    /// Lines end in a NUL byte instead of a newline, both in the files and
+    /// in the output, like `grep -z`. Useful for `find -print0` output.
+    pub null_data: bool,
+    /// How many files to search at the same time. With more than one, the
+    /// output of each file is kept together, but files come out in the
+    /// order they are finished.
+    pub threads: usize,
+    /// Match the query against file paths.
+    pub search_names: bool,
+    /// Match the query against file contents. On unless only `--name` is given.
+    pub search_contents: bool,
+}
+
+/// What happens when one of several files can't be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorMode {
+    /// Stop at the first error (`--stop-on-error`).
+    Stop,
+    /// Print the error to stderr and go on with the next file. [`run`]
+    /// still fails with [`FileErrors`] at the end (`--continue-on-error`).
+    #[default]
+    Continue,
+}
+
+/// How `--sort-matches` orders the lines of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SortOrder {
+    /// `--sort-matches`, from `A` to `z`.
+    Ascending,
+    /// `--sort-matches=reverse`.
+    Descending,
+}
+
+/// Returned by [`run`] when some files couldn't be read and the search went
+/// on without them. The binary exits with status 2 for this.
+#[derive(Debug, PartialEq)]
+pub struct FileErrors(pub u64);
+
+impl fmt::Display for FileErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let files = if self.0 == 1 { "file" } else { "files" };
+        write!(f, "{} {} couldn't be read", self.0, files)
+    }
+}
+
+impl Error for FileErrors {}
+
+/// Why the command line couldn't be turned into a [`Config`].
+#[derive(Debug, PartialEq)]
+pub enum ConfigError {
+    /// A flag or argument was missing or didn't make sense.
+    Usage(&'static str),
+    /// The query (or one of the `-e` patterns) was empty.
+    ///
+    /// An empty string is contained in every line, so an empty query would
+    /// print the whole file. That is almost always a mistake, like an unset
+    /// shell variable, so it is rejected unless `--allow-empty` is given, in
+    /// which case minigrep prints every line like `cat`.
+    EmptyQuery,
+    /// The config file was missing or couldn't be parsed.
+    File(String),
+    /// A file (or with `-r`, a directory) to search doesn't exist, found by
+    /// [`Config::validate`].
+    FileNotFound(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Usage(message) => write!(f, "{}", message),
+            ConfigError::EmptyQuery => {
+                write!(f, "The query is empty; pass --allow-empty to print every line")
+            }
+            ConfigError::File(message) => write!(f, "Couldn't load the config file: {}", message),
+            ConfigError::FileNotFound(path) => write!(f, "{}: No such file or directory", path),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl From<&'static str> for ConfigError {
+    fn from(message: &'static str) -> ConfigError {
+        ConfigError::Usage(message)
+    }
+}
+
+impl Default for Config {
+    /// The settings used when no flags are given. The query and file name
+    /// are empty.
+    fn default() -> Config {
+        Config {
+            query: String::new(),
+            patterns: Vec::new(),
+            filename: String::new(),
+            filenames: Vec::new(),
+            case_sensitive: true,
+            smart_case: false,
+            replace: None,
+            in_place: false,
+            backup: None,
+            output_dir: None,
+            zip: None,
+            dry_run: false,
+            recursive: false,
+            follow_symlinks: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            max_filesize: None,
+            verbose: false,
+            progress: false,
+            before_context: 0,
+            after_context: 0,
+            max_count: None,
+            follow: false,
+            interactive: false,
+            query_from_stdin: false,
+            stats: false,
+            first_only: false,
+            sort_matches: None,
+            replace_count: false,
+            count: false,
+            count_bytes: false,
+            invert: false,
+            word: false,
+            show_occurrences: false,
+            max_output: None,
+            max_columns: None,
+            trim: false,
+            byte_offset: false,
+            context_line_numbers: false,
+            align: false,
+            field_separator: String::from(":"),
+            heading: false,
+            json: false,
+            group_separator: None,
+            files_without_match: false,
+            summary: false,
+            with_count: false,
+            histogram: false,
+            regex: false,
+            multiline: false,
+            encoding_detect: false,
+            mmap: false,
+            error_mode: ErrorMode::Continue,
+            line_buffered: false,
+            color: false,
+            null_data: false,
+            threads: 1,
+            search_names: false,
+            search_contents: true,
+        }
+    }
 }
 
 impl Config {
-    pub fn new(mut args: env::Args) -> Result<Config, &'static str> {
+    /// Parses command-line arguments. The first item is skipped, since it is
+    /// the program name in `env::args()`.
+    ///
+    /// The remaining arguments are the query and the file name, mixed with
+    /// any flags. Case-insensitive search is turned on by setting the
+    /// `CASE_INSENSITIVE` environment variable.
+    pub fn new(args: impl Iterator<Item = String>) -> Result<Config, ConfigError> {
+        Config::with_defaults(Config::default(), args)
+    }
+
+    /// Like [`Config::new`], but starts from `defaults` instead of the
+    /// built-in settings. Flags can only turn options on or replace values,
+    /// and a query or file name on the command line replaces the default one.
+    pub fn with_defaults(
+        defaults: Config,
+        mut args: impl Iterator<Item = String>,
+    ) -> Result<Config, ConfigError> {
         args.next();
 
-        let query = match args.next() {
-            Some(arg) => arg,
-            None => return Err("Didn't get a query string"),
-        };
+        let mut replace = defaults.replace;
+        let mut in_place = defaults.in_place;
+        let mut backup = defaults.backup;
+        let mut output_dir = defaults.output_dir;
+        let mut zip = defaults.zip;
+        let mut dry_run = defaults.dry_run;
+        let mut recursive = defaults.recursive;
+        let mut follow_symlinks = defaults.follow_symlinks;
+        let mut include = defaults.include;
+        let mut exclude = defaults.exclude;
+        let mut max_filesize = defaults.max_filesize;
+        let mut verbose = defaults.verbose;
+        let mut progress = defaults.progress;
+        let mut before_context = defaults.before_context;
+        let mut after_context = defaults.after_context;
+        let mut max_count = defaults.max_count;
+        let mut follow = defaults.follow;
+        let mut interactive = defaults.interactive;
+        let mut query_from_stdin = defaults.query_from_stdin;
+        let mut stats = defaults.stats;
+        let mut first_only = defaults.first_only;
+        let mut sort_matches = defaults.sort_matches;
+        let mut replace_count = defaults.replace_count;
+        let mut count = defaults.count;
+        let mut count_bytes = defaults.count_bytes;
+        let mut invert = defaults.invert;
+        let mut word = defaults.word;
+        let mut show_occurrences = defaults.show_occurrences;
+        let mut max_output = defaults.max_output;
+        let mut max_columns = defaults.max_columns;
+        let mut trim = defaults.trim;
+        let mut byte_offset = defaults.byte_offset;
+        let mut context_line_numbers = defaults.context_line_numbers;
+        let mut align = defaults.align;
+        let mut field_separator = defaults.field_separator;
+        let mut heading = defaults.heading;
+        let mut json = defaults.json;
+        let mut group_separator = defaults.group_separator;
+        let mut files_without_match = defaults.files_without_match;
+        let mut summary = defaults.summary;
+        let mut with_count = defaults.with_count;
+        let mut histogram = defaults.histogram;
+        let mut regex = defaults.regex;
+        let mut multiline = defaults.multiline;
+        let mut encoding_detect = defaults.encoding_detect;
+        let mut mmap = defaults.mmap;
+        let mut line_buffered = defaults.line_buffered;
+        let mut null_data = defaults.null_data;
+        let mut threads = defaults.threads;
+        let mut error_mode = defaults.error_mode;
+        let mut smart_case = defaults.smart_case;
+        let mut search_names = defaults.search_names;
+        let mut content_flag = defaults.search_names && defaults.search_contents;
+        let mut patterns = Vec::new();
+        let mut patterns_given = false;
+        let mut allow_empty = false;
+        let mut positional = Vec::new();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--replace" => match args.next() {
+                    Some(arg) => replace = Some(arg),
+                    None => return Err(ConfigError::Usage("Didn't get a replacement string")),
+                },
+                "--in-place" => in_place = true,
+                "--backup" => match args.next() {
+                    Some(arg) => backup = Some(arg),
+                    None => return Err(ConfigError::Usage("Didn't get a backup suffix")),
+                },
+                "--output-dir" => match args.next() {
+                    Some(arg) => output_dir = Some(arg),
+                    None => return Err(ConfigError::Usage("Didn't get an output directory")),
+                },
+                "--zip" => match args.next() {
+                    Some(arg) => zip = Some(arg),
+                    None => return Err(ConfigError::Usage("Didn't get a ZIP archive")),
+                },
+                "--dry-run" => dry_run = true,
+                "-r" | "--recursive" => recursive = true,
+                "--follow" => follow_symlinks = true,
+                "--include" => match args.next() {
+                    Some(arg) => include.push(arg),
+                    None => return Err(ConfigError::Usage("Didn't get a glob after --include")),
+                },
+                "--exclude" => match args.next() {
+                    Some(arg) => exclude.push(arg),
+                    None => return Err(ConfigError::Usage("Didn't get a glob after --exclude")),
+                },
+                "--max-filesize" => match args.next().as_deref().and_then(filter::parse_size) {
+                    Some(size) => max_filesize = Some(size),
+                    None => {
+                        return Err(ConfigError::Usage(
+                            "Expected a size like 500K, 10M or 2G after --max-filesize",
+                        ))
+                    }
+                },
+                "--verbose" => verbose = true,
+                "--progress" => progress = true,
+                "-A" | "--after-context" => after_context = parse_number(args.next())?,
+                "-B" | "--before-context" => before_context = parse_number(args.next())?,
+                "-C" | "--context" => {
+                    before_context = parse_number(args.next())?;
+                    after_context = before_context;
+                }
+                "-m" | "--max-count" => max_count = Some(parse_number(args.next())?),
+                "--max-output-lines" => max_output = Some(parse_number(args.next())?),
+                "--max-columns" => max_columns = Some(parse_number(args.next())?),
+                "--trim" => trim = true,
+                "-f" | "--tail" => follow = true,
+                "--interactive" => interactive = true,
+                "--stats" => stats = true,
+                "--first" => first_only = true,
+                "--sort-matches" => sort_matches = Some(SortOrder::Ascending),
+                "--sort-matches=reverse" => sort_matches = Some(SortOrder::Descending),
+                "--replace-count" => replace_count = true,
+                "-c" | "--count" => count = true,
+                "--count-bytes" => {
+                    count = true;
+                    count_bytes = true;
+                }
+                "-v" | "--invert-match" => invert = true,
+                "-w" | "--word-regexp" => word = true,
+                // short flags aren't combined in general, but grep users
+                // write these two together all the time
+                "-vc" | "-cv" => {
+                    invert = true;
+                    count = true;
+                }
+                "-b" | "--byte-offset" => byte_offset = true,
+                "-n" | "--line-number" => context_line_numbers = true,
+                "--align" => align = true,
+                "--count-per-line" => show_occurrences = true,
+                "--field-separator" => match args.next() {
+                    Some(arg) => field_separator = unescape(&arg),
+                    None => return Err(ConfigError::Usage("Didn't get a field separator")),
+                },
+                "--heading" => heading = true,
+                "--json" => json = true,
+                "--group-separator" => match args.next() {
+                    Some(arg) => group_separator = Some(unescape(&arg)),
+                    None => return Err(ConfigError::Usage("Didn't get a group separator")),
+                },
+                "-L" | "--files-without-match" => files_without_match = true,
+                "--summary" => summary = true,
+                "--with-count" => with_count = true,
+                "--histogram" => histogram = true,
+                "-E" | "--regex" => regex = true,
+                "--multiline" => multiline = true,
+                "--encoding-detect" => encoding_detect = true,
+                "--mmap" => mmap = true,
+                "--line-buffered" => line_buffered = true,
+                "-z" | "--null-data" => null_data = true,
+                "-j" | "--threads" => threads = parse_number(args.next())?,
+                "--stop-on-error" => error_mode = ErrorMode::Stop,
+                "--continue-on-error" => error_mode = ErrorMode::Continue,
+                "-S" | "--smart-case" => smart_case = true,
+                "--name" => search_names = true,
+                "--content" => content_flag = true,
+                "-e" | "--pattern" => {
+                    patterns_given = true;
+                    match args.next() {
+                        Some(arg) => patterns.push(arg),
+                        None => return Err(ConfigError::Usage("Didn't get a pattern after -e")),
+                    }
+                }
+                "--allow-empty" => allow_empty = true,
+                "--query-stdin" => query_from_stdin = true,
+                "--pattern-file" => {
+                    patterns_given = true;
+                    let path = match args.next() {
+                        Some(arg) => arg,
+                        None => return Err(ConfigError::Usage("Didn't get a pattern file")),
+                    };
+                    match patterns::read_patterns(Path::new(&path)) {
+                        Ok(read) => patterns.extend(read),
+                        Err(_) => return Err(ConfigError::Usage("Couldn't read the pattern file")),
+                    }
+                }
+                // everything after `--` is the query or a file, even when
+                // it starts with a `-`
+                "--" => {
+                    positional.extend(args.by_ref());
+                    break;
+                }
+                _ => positional.push(arg),
+            }
+        }
+
+        let mut positional = positional.into_iter();
 
-        let filename = match args.next() {
-            Some(arg) => arg,
-            None => return Err("Didn't get a file name"),
+        if query_from_stdin && patterns_given {
+            return Err(ConfigError::Usage(
+                "--query-stdin can't be used with -e or --pattern-file",
+            ));
+        }
+
+        // with -e or --pattern-file, every positional argument is a file,
+        // and with --query-stdin the query comes later
+        let query = if query_from_stdin {
+            String::new()
+        } else if patterns_given {
+            match patterns.first() {
+                Some(pattern) => pattern.clone(),
+                None => return Err(ConfigError::Usage("Didn't get any patterns")),
+            }
+        } else {
+            match positional.next() {
+                Some(arg) => {
+                    patterns.push(arg.clone());
+                    arg
+                }
+                None if !defaults.patterns.is_empty() => {
+                    patterns = defaults.patterns;
+                    patterns[0].clone()
+                }
+                None if !defaults.query.is_empty() => {
+                    patterns.push(defaults.query.clone());
+                    defaults.query
+                }
+                None => return Err(ConfigError::Usage("Didn't get a query string")),
+            }
         };
 
-        let case_sensitive = env::var("CASE_INSENSITIVE").is_err();
+        // typing starts from an empty query just fine
+        if !(allow_empty || interactive) && patterns.iter().any(|pattern| pattern.is_empty()) {
+            return Err(ConfigError::EmptyQuery);
+        }
+
+        let mut filenames: Vec<String> = positional.collect();
+        if let Some(archive) = &zip {
+            if !filenames.is_empty() {
+                return Err(ConfigError::Usage(
+                    "--zip searches one archive, it can't be given files too",
+                ));
+            }
+            filenames = vec![archive.clone()];
+        }
+        if filenames.is_empty() {
+            filenames = if !defaults.filenames.is_empty() {
+                defaults.filenames
+            } else if !defaults.filename.is_empty() {
+                vec![defaults.filename]
+            } else {
+                return Err(ConfigError::Usage("Didn't get a file name"));
+            };
+        }
+        let filename = filenames[0].clone();
+
+        if in_place && replace.is_none() {
+            return Err(ConfigError::Usage("--in-place needs a --replace string"));
+        }
+        if backup.is_some() && !in_place {
+            return Err(ConfigError::Usage("--backup only works together with --in-place"));
+        }
+        if output_dir.is_some() && (replace.is_none() || in_place) {
+            return Err(ConfigError::Usage(
+                "--output-dir needs a --replace string and can't be used with --in-place",
+            ));
+        }
+        if interactive
+            && (filenames.len() > 1 || recursive || follow || replace.is_some() || count)
+        {
+            return Err(ConfigError::Usage(
+                "--interactive filters one file, it can't be used with -r, -f, --replace or -c",
+            ));
+        }
+        if zip.is_some()
+            && (recursive || follow || interactive || in_place || output_dir.is_some())
+        {
+            return Err(ConfigError::Usage(
+                "--zip can't be used with -r, -f, --interactive, --in-place or --output-dir",
+            ));
+        }
+        if zip.is_some() && search_names {
+            return Err(ConfigError::Usage("--zip only searches the contents, not --name"));
+        }
+        if follow_symlinks && !recursive {
+            return Err(ConfigError::Usage("--follow only works together with -r"));
+        }
+        if (!include.is_empty() || !exclude.is_empty()) && !recursive {
+            return Err(ConfigError::Usage(
+                "--include and --exclude only work together with -r",
+            ));
+        }
+        if max_filesize.is_some() && !recursive {
+            return Err(ConfigError::Usage("--max-filesize only works together with -r"));
+        }
+        if dry_run && replace.is_none() {
+            return Err(ConfigError::Usage("--dry-run needs a --replace string"));
+        }
+        if invert && (first_only || multiline || replace.is_some()) {
+            return Err(ConfigError::Usage(
+                "-v can't be used with --first, --multiline or --replace",
+            ));
+        }
+        // --multiline and --replace don't go through the line matcher
+        if word && (multiline || replace.is_some()) {
+            return Err(ConfigError::Usage(
+                "-w can't be used with --multiline or --replace",
+            ));
+        }
+        // these work on newline-separated lines only
+        if null_data && (follow || multiline || replace.is_some()) {
+            return Err(ConfigError::Usage(
+                "-z can't be used with -f, --multiline or --replace",
+            ));
+        }
+        if threads == 0 {
+            return Err(ConfigError::Usage("-j needs at least one thread"));
+        }
+        if follow && (recursive || filenames.len() > 1) {
+            return Err(ConfigError::Usage("-f can only follow a single file"));
+        }
+        if multiline && !regex {
+            return Err(ConfigError::Usage("--multiline only works together with --regex"));
+        }
+        if multiline && follow {
+            return Err(ConfigError::Usage("-f reads line by line, it can't use --multiline"));
+        }
+        // the file would be written back as UTF-8, without its BOM
+        if encoding_detect && in_place {
+            return Err(ConfigError::Usage("--in-place can't be used with --encoding-detect"));
+        }
+        if replace_count && replace.is_none() {
+            return Err(ConfigError::Usage("--replace-count needs a --replace string"));
+        }
+        if first_only && (multiline || count || replace.is_some()) {
+            return Err(ConfigError::Usage(
+                "--first can't be used with --multiline, -c or --replace",
+            ));
+        }
+        if files_without_match && (first_only || count || replace.is_some()) {
+            return Err(ConfigError::Usage(
+                "-L only lists files, it can't be used with --first, -c or --replace",
+            ));
+        }
+        if byte_offset && (first_only || count || files_without_match) {
+            return Err(ConfigError::Usage(
+                "-b only prefixes printed lines, it can't be used with --first, -c or -L",
+            ));
+        }
+        let has_context = before_context > 0 || after_context > 0;
+        if show_occurrences
+            && (first_only || count || files_without_match || invert || has_context)
+        {
+            return Err(ConfigError::Usage(
+                "--count-per-line only prefixes matching lines, \
+                 it can't be used with --first, -c, -L, -v or context",
+            ));
+        }
+        if show_occurrences && (multiline || replace.is_some()) {
+            return Err(ConfigError::Usage(
+                "--count-per-line can't be used with --multiline or --replace",
+            ));
+        }
+        // context lines and replacements belong to places in the file
+        if sort_matches.is_some()
+            && (first_only || count || files_without_match || follow || has_context)
+        {
+            return Err(ConfigError::Usage(
+                "--sort-matches can't be used with --first, -c, -L, -f or context",
+            ));
+        }
+        if sort_matches.is_some() && (multiline || replace.is_some()) {
+            return Err(ConfigError::Usage(
+                "--sort-matches can't be used with --multiline or --replace",
+            ));
+        }
+        if json && (first_only || count || files_without_match || follow || has_context) {
+            return Err(ConfigError::Usage(
+                "--json prints matching lines, \
+                 it can't be used with --first, -c, -L, -f or context",
+            ));
+        }
+        if json && (multiline || replace.is_some() || search_names) {
+            return Err(ConfigError::Usage(
+                "--json can't be used with --multiline, --replace or --name",
+            ));
+        }
+        if json && (byte_offset || heading || show_occurrences || null_data) {
+            return Err(ConfigError::Usage(
+                "--json can't be used with -b, --heading, --count-per-line or -z",
+            ));
+        }
+        if histogram && (first_only || count || files_without_match || follow || json) {
+            return Err(ConfigError::Usage(
+                "--histogram prints a chart of the matches, \
+                 it can't be used with --first, -c, -L, -f or --json",
+            ));
+        }
+        if align && (follow || interactive || null_data) {
+            return Err(ConfigError::Usage("--align can't be used with -f, --interactive or -z"));
+        }
+        if json && trim {
+            return Err(ConfigError::Usage(
+                "--json prints whole lines, it can't be used with --trim",
+            ));
+        }
+        if count_bytes && multiline {
+            return Err(ConfigError::Usage("--count-bytes can't be used with --multiline"));
+        }
+        if count && replace.is_some() {
+            return Err(ConfigError::Usage("-c only counts, it can't be used with --replace"));
+        }
+        if multiline && replace.is_some() {
+            return Err(ConfigError::Usage("--replace works line by line, not with --multiline"));
+        }
+
+        let case_sensitive = if smart_case {
+            patterns.iter().any(|pattern| pattern.chars().any(char::is_uppercase))
+        } else {
+            defaults.case_sensitive && env::var("CASE_INSENSITIVE").is_err()
+        };
 
         Ok(Config {
             query,
+            patterns,
             filename,
+            filenames,
             case_sensitive,
+            smart_case,
+            replace,
+            in_place,
+            backup,
+            output_dir,
+            zip,
+            dry_run,
+            recursive,
+            follow_symlinks,
+            include,
+            exclude,
+            max_filesize,
+            verbose,
+            progress,
+            before_context,
+            after_context,
+            max_count,
+            follow,
+            interactive,
+            query_from_stdin,
+            stats,
+            first_only,
+            sort_matches,
+            replace_count,
+            count,
+            count_bytes,
+            invert,
+            word,
+            show_occurrences,
+            max_output,
+            max_columns,
+            trim,
+            byte_offset,
+            context_line_numbers,
+            align,
+            field_separator,
+            heading,
+            json,
+            group_separator,
+            files_without_match,
+            summary,
+            with_count,
+            histogram,
+            regex,
+            multiline,
+            encoding_detect,
+            mmap,
+            error_mode,
+            line_buffered,
+            color: defaults.color,
+            null_data,
+            threads,
+            search_names,
+            search_contents: !search_names || content_flag,
         })
     }
+
+    /// Reads the query from the first line of `reader`, for
+    /// `query_from_stdin`. The line ending is left out, and with
+    /// `smart_case` the query decides `case_sensitive` again.
+    pub fn read_query(&mut self, mut reader: impl BufRead) -> Result<(), ConfigError> {
+        let mut query = String::new();
+        if reader.read_line(&mut query).is_err() {
+            return Err(ConfigError::Usage("Couldn't read the query from stdin"));
+        }
+        let query = query.strip_suffix('\n').unwrap_or(&query);
+        let query = query.strip_suffix('\r').unwrap_or(query);
+        if query.is_empty() {
+            return Err(ConfigError::Usage("Didn't get a query on stdin"));
+        }
+
+        self.query = query.to_string();
+        self.patterns = vec![self.query.clone()];
+        if self.smart_case {
+            self.case_sensitive = self.query.chars().any(char::is_uppercase);
+        }
+        Ok(())
+    }
+
+    /// Checks that every file to search is there before any of them is
+    /// read, so that a mistyped path fails right away instead of after the
+    /// output of the files before it. With `-r`, the paths are the
+    /// directories the search starts from.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for filename in &self.filenames {
+            if fs::metadata(filename).is_err() {
+                return Err(ConfigError::FileNotFound(filename.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    // The byte at the end of every line, in the files and in the output.
+    fn line_terminator(&self) -> u8 {
+        if self.null_data {
+            b'\0'
+        } else {
+            b'\n'
+        }
+    }
+
+    /// Reads a config file written in TOML, such as a `.minigrep.toml` with
+    /// default options. Fields that are left out keep their default values.
+    #[cfg(feature = "serde")]
+    pub fn from_toml(path: &Path) -> Result<Config, ConfigError> {
+        let error = |e: String| ConfigError::File(format!("{}: {}", path.display(), e));
+        let text = fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+        toml::from_str(&text).map_err(|e| error(e.to_string()))
+    }
 }
 
-pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
-    let contents = fs::read_to_string(config.filename)?;
+// Turns `\0`, `\t` and `\\` into the characters they stand for, since a
+// NUL can't be passed as an argument at all.
+fn unescape(arg: &str) -> String {
+    let mut unescaped = String::with_capacity(arg.len());
+    let mut chars = arg.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('0') => unescaped.push('\0'),
+            Some('t') => unescaped.push('\t'),
+            Some('\\') => unescaped.push('\\'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+fn parse_number(arg: Option<String>) -> Result<usize, &'static str> {
+    match arg.map(|arg| arg.parse()) {
+        Some(Ok(number)) => Ok(number),
+        _ => Err("Expected a number after the flag"),
+    }
+}
 
-    let results = if config.case_sensitive {
-        search(&config.query, &contents)
+/// Searches the files described by `config` and writes the results to
+/// `out`. Statistics and messages about unreadable files go to stderr.
+///
+/// Returns how many files matched and how many matches there were, the
+/// numbers behind `--summary`. `out` is written in blocks unless
+/// `config.line_buffered` is set, and flushed before this returns. With
+/// `follow` set this only returns on an error.
+pub fn run(config: Config, out: &mut dyn Write) -> Result<Summary, Box<dyn Error>> {
+    if config.interactive {
+        return run_interactive(&config, out);
+    }
+    if config.follow {
+        let matcher = matcher::from_config(&config)?;
+        // runs until the process is stopped
+        follow::follow(
+            Path::new(&config.filename),
+            Duration::from_millis(250),
+            |line| matcher.is_match(line),
+            |line| writeln!(out, "{}", line),
+        )?;
+        return Ok(Summary::default());
+    }
+    if let Some(archive) = &config.zip {
+        let file = File::open(archive)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", archive, e)))?;
+        let sources = archive::zip_sources(archive, io::BufReader::new(file))?;
+        return run_sources(&config, &sources, out);
+    }
+
+    write_results(&config, out, |out, stats| scan(&config, out, stats))
+}
+
+/// Like [`run`], but searches `sources` instead of the files named in
+/// `config`, see [`Source`].
+///
+/// Every source is read whole and then searched like a file. Only their
+/// text is searched: a source has no path to match with `--name` and
+/// nothing to write back to with `--in-place` or `--output-dir`, and the
+/// options about reading files, like `-r` or `--mmap`, don't apply.
+pub fn run_sources(
+    config: &Config,
+    sources: &[Box<dyn Source>],
+    out: &mut dyn Write,
+) -> Result<Summary, Box<dyn Error>> {
+    if config.search_names || config.in_place || config.output_dir.is_some() {
+        return Err("--name, --in-place and --output-dir only work on files".into());
+    }
+    let mut searcher = Searcher::new(config)?;
+    // the entries of an archive are always named, like files found by -r
+    searcher.show_path = sources.len() > 1 || config.zip.is_some();
+    // only ever used to clear, it is never shown
+    let mut progress = Progress::stderr(false, sources.len());
+
+    write_results(config, out, |out, stats| {
+        let mut groups = Groups::new(out, searcher.group_separator());
+        for source in sources {
+            groups.next_file();
+            let mut contents = String::new();
+            let read = source.read().and_then(|mut text| text.read_to_string(&mut contents));
+            if let Err(e) = read {
+                unreadable(config, Path::new(source.name()), e, &mut progress, stats)?;
+                continue;
+            }
+            let name = Path::new(source.name());
+            searcher.search_contents(name, &contents, false, &mut groups, stats)?;
+        }
+        Ok(())
+    })
+}
+
+// Runs `search`, which writes what it finds to the writer it is given,
+// and sends that on to `out` the way the flags ask for: lined up with
+// --align, cut off after --max-output-lines and followed by the
+// --histogram chart. Then the totals are reported.
+fn write_results<F>(
+    config: &Config,
+    out: &mut dyn Write,
+    search: F,
+) -> Result<Summary, Box<dyn Error>>
+where
+    F: FnOnce(&mut dyn Write, &mut ScanStats) -> Result<(), Box<dyn Error>>,
+{
+    let mut out = output_writer(out, config.line_buffered);
+    let mut stats = ScanStats::default();
+    let terminator = config.line_terminator();
+    let mut buffered = Vec::new();
+    let mut limit = LineLimit::new(&mut out, config.max_output, terminator);
+    let result = if config.align {
+        // the widths are only known once every line has been found, and the
+        // limit counts the aligned lines, not the buffer they come from
+        search(&mut buffered, &mut stats)
+            .and_then(|()| Ok(align::align(&buffered, terminator, &mut limit)?))
     } else {
-        search_case_sensitive(&config.query, &contents)
+        search(&mut limit, &mut stats)
     };
+    match result {
+        // the files after the limit are not searched at all
+        Err(_) if limit.truncated() => eprintln!("... (output truncated)"),
+        result => result?,
+    }
+    if config.histogram {
+        write!(out, "{}", Histogram::new(&stats.per_file).render(histogram::terminal_width()))?;
+    }
+    out.flush()?;
+    report(config, &stats)
+}
 
-    for line in results {
-        println!("{}", line);
+// Prints the totals the flags ask for to stderr, and turns files that
+// couldn't be read into an error.
+fn report(config: &Config, stats: &ScanStats) -> Result<Summary, Box<dyn Error>> {
+    if config.stats {
+        eprintln!("{}", stats);
+    }
+    if config.summary {
+        eprintln!("{}", stats.summary());
+    }
+    if config.with_count {
+        eprintln!("Total: {} matches", stats.matches);
+    }
+    if config.replace_count {
+        eprintln!(
+            "{} replacements on {} lines",
+            stats.replacements, stats.replaced_lines
+        );
+    }
+    if stats.errors > 0 {
+        return Err(Box::new(FileErrors(stats.errors)));
+    }
+
+    Ok(stats.to_summary())
+}
+
+#[cfg(feature = "interactive")]
+fn run_interactive(config: &Config, out: &mut dyn Write) -> Result<Summary, Box<dyn Error>> {
+    let printed = interactive::run(config, out)?;
+    Ok(Summary {
+        files_matched: (printed > 0) as usize,
+        total_matches: printed,
+    })
+}
+
+#[cfg(not(feature = "interactive"))]
+fn run_interactive(_config: &Config, _out: &mut dyn Write) -> Result<Summary, Box<dyn Error>> {
+    Err("minigrep was built without the interactive feature".into())
+}
+
+// Like grep, output to a terminal or with --line-buffered is flushed after
+// every line, so it shows up right away in a pipeline. Otherwise it is
+// written in blocks, which is much faster for large outputs.
+fn output_writer<'a, W: Write + 'a>(out: W, line_buffered: bool) -> Box<dyn Write + 'a> {
+    if line_buffered {
+        Box::new(LineWriter::new(out))
+    } else {
+        Box::new(BufWriter::new(out))
+    }
+}
+
+// Searches every file and writes the results to `out`, adding what was
+// scanned to `stats`.
+fn scan(config: &Config, out: &mut dyn Write, stats: &mut ScanStats) -> Result<(), Box<dyn Error>> {
+    let start = Instant::now();
+    let searcher = Searcher::new(config)?;
+    let filter = PathFilter::new(&config.include, &config.exclude)?;
+    let mut files = Vec::new();
+    for filename in &config.filenames {
+        if !config.recursive {
+            files.push(PathBuf::from(filename));
+            continue;
+        }
+        match walk::collect_files(Path::new(filename), config.follow_symlinks) {
+            Ok(found) => files.extend(found.into_iter().filter(|path| {
+                filter.is_included(path.strip_prefix(filename).unwrap_or(path))
+            })),
+            Err(e) if config.error_mode == ErrorMode::Continue => {
+                eprintln!("minigrep: {}: {}", filename, e);
+                stats.errors += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    if let Some(max) = config.max_filesize {
+        // a file that can't be looked at is reported when it is searched
+        files.retain(|path| match fs::metadata(path) {
+            Ok(meta) if meta.len() > max => {
+                if config.verbose {
+                    eprintln!("minigrep: {}: skipped, bigger than --max-filesize", path.display());
+                }
+                false
+            }
+            _ => true,
+        });
     }
+    let mut progress = Progress::stderr(config.progress, files.len());
+    let mut groups = Groups::new(out, searcher.group_separator());
 
+    let result = if config.threads > 1 {
+        scan_parallel(&searcher, &files, &mut groups, &mut progress, stats)
+    } else {
+        scan_serial(&searcher, &files, &mut groups, &mut progress, stats)
+    };
+    progress.clear();
+    stats.elapsed = start.elapsed();
+    Ok(result?)
+}
+
+// Searches the files one after the other, printing the results as they are
+// found.
+fn scan_serial<W: Write>(
+    searcher: &Searcher,
+    files: &[PathBuf],
+    out: &mut Groups,
+    progress: &mut Progress<W>,
+    stats: &mut ScanStats,
+) -> io::Result<()> {
+    for (scanned, path) in files.iter().enumerate() {
+        out.next_file();
+        let mut file_out = ClearProgress {
+            out: &mut *out,
+            progress: &mut *progress,
+        };
+        if let Searched::Unreadable(e) = searcher.search_file(path, &mut file_out, stats)? {
+            unreadable(searcher.config, path, e, progress, stats)?;
+        }
+        progress.update(scanned + 1, stats.matches as usize);
+    }
     Ok(())
 }
 
+// Like `scan_serial`, but `config.threads` workers take the files in
+// turn. Each file's output is buffered and printed in one go, in the order
+// the files are finished. The workers share the totals and add each file to
+// them once, after it has been searched.
+fn scan_parallel<W: Write>(
+    searcher: &Searcher,
+    files: &[PathBuf],
+    out: &mut Groups,
+    progress: &mut Progress<W>,
+    stats: &mut ScanStats,
+) -> io::Result<()> {
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let totals = Mutex::new(mem::take(stats));
+    let (sender, receiver) = mpsc::channel();
+
+    let result = thread::scope(|scope| {
+        for _ in 0..searcher.config.threads {
+            let sender = sender.clone();
+            let (next, stop, totals) = (&next, &stop, &totals);
+            scope.spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let path = match files.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        Some(path) => path,
+                        None => break,
+                    };
+                    let mut output = Vec::new();
+                    let mut file_stats = ScanStats::default();
+                    let searched = searcher.search_file(path, &mut output, &mut file_stats);
+                    if searched.is_ok() {
+                        totals.lock().unwrap().add(&file_stats);
+                    }
+                    if sender.send((path, output, searched)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        let mut print = || -> io::Result<()> {
+            for (scanned, (path, output, searched)) in receiver.iter().enumerate() {
+                if !output.is_empty() {
+                    progress.clear();
+                    out.next_file();
+                    out.write_all(&output)?;
+                }
+                let mut totals = totals.lock().unwrap();
+                if let Searched::Unreadable(e) = searched? {
+                    unreadable(searcher.config, path, e, progress, &mut totals)?;
+                }
+                progress.update(scanned + 1, totals.matches as usize);
+            }
+            Ok(())
+        };
+        let result = print();
+        // let the workers finish early instead of searching files for nothing
+        stop.store(true, Ordering::Relaxed);
+        result
+    });
+
+    *stats = totals.into_inner().unwrap();
+    result
+}
+
+// Prints an unreadable file to stderr and counts it, or fails with
+// --stop-on-error.
+fn unreadable<W: Write>(
+    config: &Config,
+    path: &Path,
+    e: io::Error,
+    progress: &mut Progress<W>,
+    stats: &mut ScanStats,
+) -> io::Result<()> {
+    if config.error_mode == ErrorMode::Stop {
+        return Err(io::Error::new(e.kind(), format!("{}: {}", path.display(), e)));
+    }
+    progress.clear();
+    eprintln!("minigrep: {}: {}", path.display(), e);
+    stats.errors += 1;
+    Ok(())
+}
+
+// Wipes the progress line before anything is printed, so the two never get
+// mixed up.
+struct ClearProgress<'a, W: Write> {
+    out: &'a mut dyn Write,
+    progress: &'a mut Progress<W>,
+}
+
+impl<W: Write> Write for ClearProgress<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.progress.clear();
+        self.out.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+// Writes the separator between the output of two files, when the output is
+// grouped by file. Files that print nothing don't count.
+struct Groups<'a> {
+    out: &'a mut dyn Write,
+    separator: Option<Vec<u8>>,
+    // whether an earlier file printed anything, and whether this one did
+    printed: bool,
+    in_file: bool,
+}
+
+impl<'a> Groups<'a> {
+    fn new(out: &'a mut dyn Write, separator: Option<Vec<u8>>) -> Groups<'a> {
+        Groups {
+            out,
+            separator,
+            printed: false,
+            in_file: false,
+        }
+    }
+
+    fn next_file(&mut self) {
+        self.in_file = false;
+    }
+}
+
+impl Write for Groups<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.in_file && !buf.is_empty() {
+            if let Some(separator) = self.separator.as_ref().filter(|_| self.printed) {
+                self.out.write_all(separator)?;
+            }
+            self.printed = true;
+            self.in_file = true;
+        }
+        self.out.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+// What became of a file, besides what was printed for it.
+enum Searched {
+    Done,
+    // a binary file, skipped by a recursive search
+    Skipped,
+    Unreadable(io::Error),
+}
+
+// Everything that is built once per search and then used for every file.
+// It is shared between the threads with --threads.
+struct Searcher<'a> {
+    config: &'a Config,
+    matcher: Box<dyn Matcher>,
+    multiline: Option<Regex>,
+    // color the matches
+    color: bool,
+    replace_regex: Option<Regex>,
+    show_path: bool,
+}
+
+impl<'a> Searcher<'a> {
+    fn new(config: &'a Config) -> Result<Searcher<'a>, Box<dyn Error>> {
+        let multiline = if config.multiline {
+            Some(patterns::build_regex(&config.patterns, config.case_sensitive, true)?)
+        } else {
+            None
+        };
+        let replace_regex = match (&config.replace, config.regex) {
+            (Some(_), true) => Some(patterns::build_regex(
+                &config.patterns,
+                config.case_sensitive,
+                false,
+            )?),
+            _ => None,
+        };
+        Ok(Searcher {
+            config,
+            matcher: matcher::from_config(config)?,
+            multiline,
+            color: config.color,
+            replace_regex,
+            show_path: config.recursive || config.filenames.len() > 1,
+        })
+    }
+
+    // The line between two files, terminator included, when their lines are
+    // grouped: under a heading, or with context.
+    fn group_separator(&self) -> Option<Vec<u8>> {
+        let config = self.config;
+        let context = config.before_context > 0 || config.after_context > 0;
+        let lines = !(config.count || config.files_without_match || config.first_only);
+        if !(self.show_path && lines && (config.heading || context)) {
+            return None;
+        }
+        let mut separator = config.group_separator.clone().unwrap_or_default().into_bytes();
+        separator.push(config.line_terminator());
+        Some(separator)
+    }
+
+    // Searches one file, printing its results to `out` and adding it to
+    // `stats`. A file that can't be read is returned as `Unreadable`; `Err`
+    // is only for errors writing the results.
+    fn search_file(
+        &self,
+        path: &Path,
+        out: &mut dyn Write,
+        stats: &mut ScanStats,
+    ) -> io::Result<Searched> {
+        let config = self.config;
+        let name_matched = config.search_names && self.matcher.is_match(&path.to_string_lossy());
+        if name_matched {
+            if config.search_contents {
+                writeln!(out, "name: {}", path.display())?;
+            } else {
+                writeln!(out, "{}", path.display())?;
+            }
+            stats.matches += 1;
+        }
+        if !config.search_contents {
+            stats.count_file(name_matched);
+            return Ok(Searched::Done);
+        }
+        if self.streams() {
+            return self.stream_file(path, name_matched, out, stats);
+        }
+
+        let contents = if config.encoding_detect {
+            fs::read(path)
+                .and_then(|bytes| encoding::decode(&bytes))
+                .map(Contents::Read)
+        } else {
+            mmap::read(path, config.mmap)
+        };
+        let contents = match contents {
+            Ok(contents) => contents,
+            Err(e) => return Ok(self.unreadable(path, e)),
+        };
+        self.search_contents(path, &contents, name_matched, out, stats)?;
+        Ok(Searched::Done)
+    }
+
+    // What to make of `e`, which came from reading the file at `path`.
+    fn unreadable(&self, path: &Path, e: io::Error) -> Searched {
+        match e {
+            // binary files can't be searched as text, skip them
+            e if self.config.recursive && e.kind() == io::ErrorKind::InvalidData => {
+                Searched::Skipped
+            }
+            // only -r looks inside directories, say so instead of a bare
+            // "Is a directory"
+            _ if path.is_dir() => Searched::Unreadable(io::Error::new(
+                io::ErrorKind::IsADirectory,
+                "is a directory, use -r to search it",
+            )),
+            e => Searched::Unreadable(e),
+        }
+    }
+
+    // Whether files can be read a line at a time, keeping only the lines
+    // of context, instead of all at once. Only plain -A, -B and -C
+    // searches do; whatever needs the whole file, like counts,
+    // replacements, byte offsets or --stats, reads it as before.
+    fn streams(&self) -> bool {
+        let config = self.config;
+        let whole_file = config.count
+            || config.histogram
+            || config.files_without_match
+            || config.first_only
+            || config.replace.is_some()
+            || config.byte_offset
+            || config.heading
+            || config.stats
+            || config.mmap
+            || config.encoding_detect;
+        (config.before_context > 0 || config.after_context > 0)
+            && self.multiline.is_none()
+            && config.line_terminator() == b'\n'
+            && !whole_file
+    }
+
+    // Like `search_file` once the name was searched, for a search that
+    // `streams`: every line is printed as soon as it is known, and only
+    // the last lines of before-context are kept in memory.
+    fn stream_file(
+        &self,
+        path: &Path,
+        name_matched: bool,
+        out: &mut dyn Write,
+        stats: &mut ScanStats,
+    ) -> io::Result<Searched> {
+        let config = self.config;
+        let mut reader = match File::open(path) {
+            Ok(file) => io::BufReader::new(file),
+            Err(e) => return Ok(self.unreadable(path, e)),
+        };
+        // a binary file is told apart by its first block, before anything
+        // of it is printed; past that, it is only noticed at the first
+        // bytes that aren't UTF-8
+        let start = match reader.fill_buf() {
+            // a character cut off at the end of the block is fine
+            Ok(start) => std::str::from_utf8(start)
+                .err()
+                .filter(|e| e.error_len().is_some())
+                .map(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) => Some(e),
+        };
+        if let Some(e) = start {
+            return Ok(self.unreadable(path, e));
+        }
+
+        let matcher = self.matcher.as_ref();
+        let pattern = Pattern::Literal(&config.query);
+        let mut matches = 0;
+        let mut write_failed = false;
+        let read = context::stream_with_context(
+            reader,
+            |line| matcher.is_match(line) != config.invert,
+            config.before_context,
+            config.after_context,
+            config.max_count,
+            |line| {
+                if let Line::Match(..) = line {
+                    matches += 1;
+                }
+                // nothing in `output` needs the contents for these searches
+                let show_path = self.show_path;
+                output(out, config, pattern, path, show_path, "", &[line], matcher, self.color)
+                    .map(|_| ())
+                    .inspect_err(|_| write_failed = true)
+            },
+        );
+        match read {
+            Err(e) if write_failed => return Err(e),
+            Err(e) => return Ok(self.unreadable(path, e)),
+            Ok(()) => {}
+        }
+        stats.matches += matches as u64;
+        stats.count_file(name_matched || matches > 0);
+        Ok(Searched::Done)
+    }
+
+    // The part of `search_file` after the file was read, which doesn't care
+    // where the text came from. `path` is only printed.
+    fn search_contents(
+        &self,
+        path: &Path,
+        contents: &str,
+        name_matched: bool,
+        out: &mut dyn Write,
+        stats: &mut ScanStats,
+    ) -> io::Result<()> {
+        let config = self.config;
+        let show_path = self.show_path;
+        if config.first_only {
+            // stops reading at the first match
+            let first = context::split_lines(contents, config.line_terminator())
+                .enumerate()
+                .find_map(|(i, line)| {
+                    let start = self.matcher.find_ranges(line).first()?.start;
+                    // columns count characters from 1
+                    Some((i + 1, line[..start].chars().count() + 1, line))
+                });
+            if let Some((number, column, line)) = first {
+                let sep = &config.field_separator;
+                if show_path {
+                    write!(out, "{}{}", path.display(), sep)?;
+                }
+                let line = truncate(trim(line, config.trim).0, config.max_columns);
+                write!(out, "{}{}{}{}{}", number, sep, column, sep, line)?;
+                out.write_all(&[config.line_terminator()])?;
+            }
+            stats.add_file(contents, first.is_some() as usize);
+            stats.count_file(name_matched || first.is_some());
+            return Ok(());
+        }
+        let mut results = match &self.multiline {
+            // -c and --histogram only need the number, so no lines are
+            // collected for them
+            None if config.count || config.histogram => Vec::new(),
+            Some(regex) => context::search_multiline(
+                contents,
+                regex,
+                config.before_context,
+                config.after_context,
+                config.max_count,
+            ),
+            None => find_matches(config, self.matcher.as_ref(), contents),
+        };
+        if let Some(order) = config.sort_matches {
+            sort_lines(&mut results, order);
+        }
+        let counted = config.count || config.histogram;
+        let matches = if counted && self.multiline.is_none() {
+            self.count_lines(contents)
+        } else {
+            results
+                .iter()
+                .filter(|line| matches!(line, Line::Match(..)))
+                .count()
+        };
+        if config.files_without_match {
+            // every line was searched, so no match means there is none
+            if matches == 0 {
+                writeln!(out, "{}", path.display())?;
+            }
+        } else if config.histogram {
+            // drawn once every file has been searched
+            stats.per_file.push((path.to_path_buf(), matches as u64));
+        } else if config.count {
+            let number = if config.count_bytes {
+                self.count_bytes(contents)
+            } else {
+                matches
+            };
+            if show_path {
+                writeln!(out, "{}{}{}", path.display(), config.field_separator, number)?;
+            } else {
+                writeln!(out, "{}", number)?;
+            }
+        } else if !results.is_empty() {
+            let replace_pattern = match &self.replace_regex {
+                Some(regex) => Pattern::Regex(regex),
+                None => Pattern::Literal(&config.query),
+            };
+            let replaced = output(
+                out,
+                config,
+                replace_pattern,
+                path,
+                show_path,
+                contents,
+                &results,
+                self.matcher.as_ref(),
+                self.color,
+            )?;
+            stats.replacements += replaced.replacements as u64;
+            stats.replaced_lines += replaced.lines as u64;
+        }
+        stats.add_file(contents, matches);
+        stats.count_file(name_matched || matches > 0);
+        Ok(())
+    }
+
+    // The number -c prints: the matching lines, or with -v all the others.
+    // Both are counted in one pass, and the inverted count is simply the
+    // total minus the matching lines. A single literal pattern is counted
+    // by `count_matches`, so the library and -c can't disagree.
+    fn count_lines(&self, contents: &str) -> usize {
+        let config = self.config;
+        let terminator = config.line_terminator();
+        let (total, matching) = match literal(config) {
+            Some(query) if terminator == b'\n' => {
+                let matching = count_matches(query, contents, !config.case_sensitive);
+                // only -v needs the other lines
+                let total = if config.invert { contents.lines().count() } else { 0 };
+                (total, matching)
+            }
+            _ => {
+                let mut total = 0;
+                let mut matching = 0;
+                for line in context::split_lines(contents, terminator) {
+                    total += 1;
+                    if self.matcher.is_match(line) {
+                        matching += 1;
+                    }
+                }
+                (total, matching)
+            }
+        };
+        let count = if self.config.invert {
+            total - matching
+        } else {
+            matching
+        };
+        match self.config.max_count {
+            Some(max) => count.min(max),
+            None => count,
+        }
+    }
+
+    // The number --count-bytes prints: the length of every line -c counts,
+    // plus one for its line ending.
+    fn count_bytes(&self, contents: &str) -> usize {
+        context::split_lines(contents, self.config.line_terminator())
+            .filter(|line| self.matcher.is_match(line) != self.config.invert)
+            .take(self.config.max_count.unwrap_or(usize::MAX))
+            .map(|line| line.len() + 1)
+            .sum()
+    }
+}
+
+fn find_matches<'a>(
+    config: &Config,
+    matcher: &dyn Matcher,
+    contents: &'a str,
+) -> Vec<Line<'a>> {
+    context::search_records(
+        contents,
+        config.line_terminator(),
+        |line| matcher.is_match(line) != config.invert,
+        config.before_context,
+        config.after_context,
+        config.max_count,
+    )
+}
+
+// Sorts matching lines by their text; lines with the same text stay in file
+// order. There are no context lines or separators, since `--sort-matches`
+// can't be used with context.
+fn sort_lines(lines: &mut [Line], order: SortOrder) {
+    fn text<'a>(line: &Line<'a>) -> &'a str {
+        match *line {
+            Line::Match(_, text) | Line::Context(_, text) => text,
+            Line::Separator => "",
+        }
+    }
+    match order {
+        SortOrder::Ascending => lines.sort_by(|a, b| text(a).cmp(text(b))),
+        SortOrder::Descending => lines.sort_by(|a, b| text(b).cmp(text(a))),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn output(
+    out: &mut dyn Write,
+    config: &Config,
+    pattern: Pattern,
+    path: &Path,
+    show_path: bool,
+    contents: &str,
+    results: &[Line],
+    matcher: &dyn Matcher,
+    color: bool,
+) -> io::Result<ReplaceCount> {
+    if let Some(replacement) = &config.replace {
+        // a dry run never writes, even when --in-place is given
+        if config.dry_run {
+            return replace::write_preview(out, pattern, contents, replacement, color);
+        }
+        if config.in_place {
+            let (replaced, count) = replace::replace_all(pattern, contents, replacement);
+            if replaced != contents {
+                replace::write_in_place(path, &replaced, config.backup.as_deref())?;
+            }
+            return Ok(count);
+        }
+        // only files with a replacement are copied
+        if let Some(dir) = &config.output_dir {
+            let (replaced, count) = replace::replace_all(pattern, contents, replacement);
+            if replaced != contents {
+                let target = Path::new(dir).join(relative_path(config, path));
+                replace::write_copy(&target, &replaced)?;
+            }
+            return Ok(count);
+        }
+    }
+
+    let mut count = ReplaceCount::default();
+
+    if config.json {
+        for line in results {
+            if let Line::Match(number, text) = *line {
+                let ranges = match literal(config) {
+                    Some(query) if config.case_sensitive => match_ranges(query, text),
+                    _ => matcher.find_ranges(text),
+                };
+                json::write_match(out, path, number, text, &ranges)?;
+            }
+        }
+        return Ok(count);
+    }
+
+    // label content matches when file name matches are printed too
+    let label = if config.search_names { "content: " } else { "" };
+    let starts = if config.byte_offset {
+        context::line_starts(contents, config.line_terminator())
+    } else {
+        Vec::new()
+    };
+
+    let separator = config.field_separator.as_str();
+    let terminator = config.line_terminator();
+    let heading = show_path && config.heading;
+    if heading && !results.is_empty() {
+        write!(out, "{}{}", label, path.display())?;
+        out.write_all(&[terminator])?;
+    }
+    for line in results {
+        let (number, text, separator) = match *line {
+            Line::Match(number, text) => match &config.replace {
+                Some(replacement) => {
+                    let (replaced, replacements) =
+                        replace::apply_replacement(pattern, text, replacement);
+                    count.add_line(replacements);
+                    let replaced = trim(&replaced, config.trim).0;
+                    (number, truncate(replaced, config.max_columns), separator)
+                }
+                None if color => {
+                    // the matches are found in the whole line, then moved
+                    // to where they are in what gets printed
+                    let (trimmed, start) = trim(text, config.trim);
+                    let shown = clip(trimmed, config.max_columns);
+                    let end = start + shown.len();
+                    // only color what is left of the matches
+                    let ranges: Vec<_> = matcher
+                        .find_ranges(text)
+                        .into_iter()
+                        .filter(|range| range.start < end && range.end > start)
+                        .map(|range| range.start.max(start) - start..range.end.min(end) - start)
+                        .collect();
+                    let mut highlighted = color::highlight(shown, &ranges, Color::Red);
+                    if shown.len() < trimmed.len() {
+                        highlighted.push('…');
+                    }
+                    (number, highlighted, separator)
+                }
+                None => {
+                    let text = trim(text, config.trim).0;
+                    (number, truncate(text, config.max_columns), separator)
+                }
+            },
+            Line::Context(number, text) => (number, truncate(text, config.max_columns), "-"),
+            Line::Separator => {
+                write!(out, "{}--", label)?;
+                out.write_all(&[terminator])?;
+                continue;
+            }
+        };
+        // what goes in front of the text, in order
+        let mut fields = Vec::new();
+        if show_path && !heading {
+            fields.push((Field::Name, path.display().to_string()));
+        }
+        match *line {
+            Line::Match(_, line) if config.show_occurrences => {
+                match matcher.find_ranges(line).len() {
+                    0 => continue,
+                    n => fields.extend([
+                        (Field::Number, number.to_string()),
+                        (Field::Number, n.to_string()),
+                    ]),
+                }
+            }
+            // --count-per-line has the number already
+            _ if config.context_line_numbers && !config.show_occurrences => {
+                fields.push((Field::Number, number.to_string()));
+            }
+            _ => {}
+        }
+        if config.byte_offset {
+            fields.push((Field::Number, starts[number - 1].to_string()));
+        }
+        if config.align {
+            // every line gets a row, so no text is taken for one
+            let mut row = Vec::new();
+            if !label.is_empty() {
+                row.push((Field::Name, label, ""));
+            }
+            row.extend(fields.iter().map(|(kind, field)| (*kind, field.as_str(), separator)));
+            align::write_fields(out, &row)?;
+        } else {
+            write!(out, "{}", label)?;
+            for (_, field) in &fields {
+                write!(out, "{}{}", field, separator)?;
+            }
+        }
+        write!(out, "{}", text)?;
+        out.write_all(&[terminator])?;
+    }
+
+    Ok(count)
+}
+
+// Where `path` is inside the searched directory it was found in, or just its
+// name when it was named on the command line.
+fn relative_path<'p>(config: &Config, path: &'p Path) -> &'p Path {
+    config
+        .filenames
+        .iter()
+        .filter(|root| Path::new(root) != path)
+        .find_map(|root| path.strip_prefix(root).ok())
+        .or_else(|| path.file_name().map(Path::new))
+        .unwrap_or(path)
+}
+
+// `line` without the whitespace around it when `trim` is set, and how many
+// bytes were cut off its start.
+fn trim(line: &str, trim: bool) -> (&str, usize) {
+    if !trim {
+        return (line, 0);
+    }
+    let start = line.len() - line.trim_start().len();
+    (line.trim(), start)
+}
+
+// Cuts `line` down to `max_columns` characters, or returns all of it.
+fn clip(line: &str, max_columns: Option<usize>) -> &str {
+    match max_columns.and_then(|max| line.char_indices().nth(max)) {
+        Some((end, _)) => &line[..end],
+        None => line,
+    }
+}
+
+// Like `clip`, but with an ellipsis in place of what was cut off.
+fn truncate(line: &str, max_columns: Option<usize>) -> String {
+    let clipped = clip(line, max_columns);
+    if clipped.len() < line.len() {
+        format!("{}…", clipped)
+    } else {
+        line.to_string()
+    }
+}
+
+/// Returns every line of `contents` that contains `query`, matching case.
 pub fn search<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
     contents
         .lines()
@@ -55,22 +1888,132 @@ pub fn search<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
         .collect()
 }
 
-// write a search_case_sensitive function 
-pub fn search_case_sensitive<'a>(
+/// Counts the lines of `contents` that contain `query`. `minigrep -c`
+/// counts with this whenever it looks for a single literal pattern.
+///
+/// Gives the same number as `search(query, contents).len()`, or
+/// [`search_case_insensitive`] with `case_insensitive`, without collecting
+/// the lines into a `Vec`.
+pub fn count_matches(query: &str, contents: &str, case_insensitive: bool) -> usize {
+    if case_insensitive {
+        let query = query.to_lowercase();
+        let mut lowercase = String::new();
+        count_lines(contents, |line| contains_ignore_case(line, &query, &mut lowercase))
+    } else {
+        count_lines(contents, |line| line.contains(query))
+    }
+}
+
+fn count_lines(contents: &str, mut is_match: impl FnMut(&str) -> bool) -> usize {
+    contents.lines().filter(|line| is_match(line)).count()
+}
+
+/// Searches every file in `files` for `query` and returns the matching lines
+/// of each, with their line numbers counted from 1.
+///
+/// Every file that was searched gets an entry, an empty one if nothing
+/// matched. Files that can't be opened, e.g. because they don't exist, are
+/// skipped and have no entry at all. A file that opens but can't be read as
+/// text, like a binary file, is an error.
+pub fn search_files(
+    query: &str,
+    files: &[PathBuf],
+    case_insensitive: bool,
+) -> io::Result<HashMap<PathBuf, Vec<(usize, String)>>> {
+    let lowercase_query = query.to_lowercase();
+    let is_match = |line: &str| {
+        if case_insensitive {
+            line.to_lowercase().contains(&lowercase_query)
+        } else {
+            line.contains(query)
+        }
+    };
+
+    let mut found = HashMap::new();
+    for path in files {
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => continue,
+        };
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+
+        let matches = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| is_match(line))
+            .map(|(i, line)| (i + 1, line.to_string()))
+            .collect();
+        found.insert(path.clone(), matches);
+    }
+    Ok(found)
+}
+
+// The one pattern of a search for a plain string: not a regex, not -w and
+// not one of several patterns.
+fn literal(config: &Config) -> Option<&str> {
+    match config.patterns.as_slice() {
+        [query] if !(config.regex || config.word) => Some(query),
+        _ => None,
+    }
+}
+
+/// Returns the byte range of every occurrence of `query` in `line`.
+///
+/// Occurrences are found left to right and don't overlap: searching `"aa"`
+/// in `"aaaa"` gives `0..2` and `2..4`. The ranges always fall on character
+/// boundaries, so `&line[range]` is safe even for multibyte text. An empty
+/// query has no ranges.
+pub fn match_ranges(query: &str, line: &str) -> Vec<Range<usize>> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    line.match_indices(query)
+        .map(|(start, found)| start..start + found.len())
+        .collect()
+}
+
+/// Like [`search`], but ignores case.
+///
+/// Both the query and each line are lowercased with `str::to_lowercase`,
+/// which follows the Unicode lowercase mapping: `É` matches `é`, `ẞ` matches
+/// `ß`, and `İ` lowercases to the two characters `i̇` on both sides. This is
+/// not full case folding, though, and it doesn't depend on the locale:
+/// `ß` won't match `ss`, and the Turkish dotless `ı` won't match `I`.
+///
+/// The query is lowercased once. Lines that are plain ASCII, like most
+/// lines of code and logs, are lowercased into one buffer reused for every
+/// line instead of a new `String` each.
+pub fn search_case_insensitive<'a>(
     query: &str,
     contents: &'a str,
     ) -> Vec<&'a str>{
     let query = query.to_lowercase();
+    let mut lowercase = String::new();
     let mut results = Vec::new();
 
     for line in contents.lines() {
-        if line.to_lowercase().contains(&query){
+        if contains_ignore_case(line, &query, &mut lowercase) {
             results.push(line);
         }
     }
     results
 }
 
+// Whether `line` lowercased contains `lowercase_query`. Lowercasing ASCII only
+// touches A-Z, so it can be done in `buffer`; other characters can lowercase
+// to several, some of them ASCII (the Kelvin sign to `k`, `İ` to `i` and a
+// combining dot), so any other line goes through `to_lowercase`.
+fn contains_ignore_case(line: &str, lowercase_query: &str, buffer: &mut String) -> bool {
+    if !line.is_ascii() {
+        return line.to_lowercase().contains(lowercase_query);
+    }
+    buffer.clear();
+    buffer.push_str(line);
+    buffer.make_ascii_lowercase();
+    buffer.contains(lowercase_query)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -96,7 +2039,481 @@ Pick three.
 Trust me.";
 
 assert_eq!(
-            vec!["Rust:", "Trust me."], search_case_sensitive(query, contents)
+            vec!["Rust:", "Trust me."], search_case_insensitive(query, contents)
+            );
+    }
+
+    fn args(list: &[&str]) -> impl Iterator<Item = String> {
+        let mut args = vec![String::from("minigrep")];
+        args.extend(list.iter().map(|arg| arg.to_string()));
+        args.into_iter()
+    }
+
+    fn scanned(config: &Config, out: &mut dyn Write) -> ScanStats {
+        let mut stats = ScanStats::default();
+        scan(config, out, &mut stats).unwrap();
+        stats
+    }
+
+    #[test]
+    fn scan_counts_a_known_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("poem.txt");
+        fs::write(&path, "I'm nobody! Who are you?\nAre you nobody, too?\nHow dreary\n").unwrap();
+
+        let config = Config::new(args(&["nobody", path.to_str().unwrap()])).unwrap();
+        let stats = scanned(&config, &mut io::sink());
+
+        assert_eq!(57, stats.bytes);
+        assert_eq!(3, stats.lines);
+        assert_eq!(2, stats.matches);
+    }
+
+    #[test]
+    fn parallel_scan_matches_the_serial_one() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..20 {
+            let mut contents = String::new();
+            for line in 0..i * 10 {
+                if line % 3 == 0 {
+                    contents.push_str("I'm nobody! Who are you?\n");
+                } else {
+                    contents.push_str("How dreary to be somebody!\n");
+                }
+            }
+            fs::write(dir.path().join(format!("{}.txt", i)), contents).unwrap();
+        }
+        let root = dir.path().to_str().unwrap();
+
+        let config = Config::new(args(&["-r", "nobody", root])).unwrap();
+        let mut serial_out = Vec::new();
+        let serial = scanned(&config, &mut serial_out);
+
+        let config = Config::new(args(&["-r", "-j", "4", "nobody", root])).unwrap();
+        let mut parallel_out = Vec::new();
+        let parallel = scanned(&config, &mut parallel_out);
+
+        assert_eq!(640, serial.matches);
+        assert_eq!(19, serial.files_with_matches);
+        assert_eq!(
+            ScanStats {
+                elapsed: serial.elapsed,
+                ..parallel
+            },
+            serial
+        );
+        // the files may come out in any order, but each one stays together
+        let mut serial_lines: Vec<_> = serial_out.split(|&b| b == b'\n').collect();
+        let mut parallel_lines: Vec<_> = parallel_out.split(|&b| b == b'\n').collect();
+        serial_lines.sort();
+        parallel_lines.sort();
+        assert_eq!(serial_lines, parallel_lines);
+    }
+
+    #[test]
+    fn field_separator_escapes() {
+        assert_eq!("\0", unescape("\\0"));
+        assert_eq!("|", unescape("|"));
+        assert_eq!("\t:\\", unescape("\\t:\\\\"));
+        assert_eq!("\\n\\", unescape("\\n\\"));
+    }
+
+    #[test]
+    fn truncation_keeps_whole_characters() {
+        assert_eq!("Ὀδυσ…", truncate("Ὀδυσσεύς", Some(4)));
+        assert_eq!("Ὀδυσσεύς", truncate("Ὀδυσσεύς", Some(8)));
+        assert_eq!("…", truncate("nobody", Some(0)));
+        assert_eq!("nobody", truncate("nobody", None));
+    }
+
+    #[test]
+    fn zero_threads_are_rejected() {
+        assert_eq!(
+            ConfigError::Usage("-j needs at least one thread"),
+            Config::new(args(&["-j", "0", "nobody", "poem.txt"])).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn line_buffered_output_is_flushed_per_line() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        // a writer whose contents can still be checked while it is wrapped
+        #[derive(Clone, Default)]
+        struct Shared(Rc<RefCell<Vec<u8>>>);
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.borrow_mut().write(buf)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let written = Shared::default();
+        let mut out = output_writer(written.clone(), true);
+        writeln!(out, "I'm nobody!").unwrap();
+        assert_eq!(b"I'm nobody!\n", &written.0.borrow()[..]);
+
+        let written = Shared::default();
+        let mut out = output_writer(written.clone(), false);
+        writeln!(out, "I'm nobody!").unwrap();
+        assert!(written.0.borrow().is_empty());
+        out.flush().unwrap();
+        assert_eq!(b"I'm nobody!\n", &written.0.borrow()[..]);
+    }
+
+    #[test]
+    fn empty_query_is_rejected() {
+        assert_eq!(
+            Err(ConfigError::EmptyQuery),
+            Config::new(args(&["", "poem.txt"])).map(|_| ())
+        );
+        assert_eq!(
+            Err(ConfigError::EmptyQuery),
+            Config::new(args(&["-e", "nobody", "-e", "", "poem.txt"])).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn allow_empty_matches_every_line() {
+        let config = Config::new(args(&["--allow-empty", "", "poem.txt"])).unwrap();
+        assert_eq!("", config.query);
+
+        let contents = "\
+Rust:
+
+Trust me.";
+        assert_eq!(vec!["Rust:", "", "Trust me."], search(&config.query, contents));
+    }
+
+    #[test]
+    fn several_patterns() {
+        let config = Config::new(args(&["-e", "nobody", "-e", "frog", "poem.txt"])).unwrap();
+        assert_eq!("nobody", config.query);
+        assert_eq!(vec!["nobody", "frog"], config.patterns);
+        assert_eq!("poem.txt", config.filename);
+
+        let config = Config::new(args(&["nobody", "poem.txt"])).unwrap();
+        assert_eq!(vec!["nobody"], config.patterns);
+    }
+
+    #[test]
+    fn in_place_flags() {
+        let config = Config::new(args(&[
+            "--replace", "somebody", "--in-place", "--backup", ".bak", "nobody", "poem.txt",
+        ]))
+        .unwrap();
+        assert_eq!("nobody", config.query);
+        assert_eq!("poem.txt", config.filename);
+        assert_eq!(Some(String::from("somebody")), config.replace);
+        assert!(config.in_place);
+        assert_eq!(Some(String::from(".bak")), config.backup);
+
+        assert!(Config::new(args(&["--in-place", "nobody", "poem.txt"])).is_err());
+    }
+
+    #[test]
+    fn command_line_overrides_defaults() {
+        let defaults = Config {
+            query: String::from("nobody"),
+            filename: String::from("poem.txt"),
+            after_context: 2,
+            stats: true,
+            ..Config::default()
+        };
+
+        let config = Config::with_defaults(defaults.clone(), args(&[])).unwrap();
+        assert_eq!("nobody", config.query);
+        assert_eq!(vec!["nobody"], config.patterns);
+        assert_eq!("poem.txt", config.filename);
+        assert_eq!(2, config.after_context);
+        assert!(config.stats);
+
+        let config = Config::with_defaults(defaults, args(&["-A", "0", "somebody"])).unwrap();
+        assert_eq!("somebody", config.query);
+        assert_eq!("poem.txt", config.filename);
+        assert_eq!(0, config.after_context);
+    }
+
+    #[test]
+    fn every_positional_argument_after_the_query_is_a_file() {
+        let config = Config::new(args(&["nobody", "a.txt", "b.txt"])).unwrap();
+        assert_eq!("a.txt", config.filename);
+        assert_eq!(vec!["a.txt", "b.txt"], config.filenames);
+        assert_eq!(ErrorMode::Continue, config.error_mode);
+
+        let config = Config::new(args(&["--stop-on-error", "nobody", "a.txt"])).unwrap();
+        assert_eq!(ErrorMode::Stop, config.error_mode);
+
+        assert!(Config::new(args(&["-f", "nobody", "a.txt", "b.txt"])).is_err());
+    }
+
+    #[test]
+    fn double_dash_ends_the_flags() {
+        let config = Config::new(args(&["nobody", "--", "-weird.txt", "-v"])).unwrap();
+        assert_eq!("nobody", config.query);
+        assert_eq!(vec!["-weird.txt", "-v"], config.filenames);
+        assert!(!config.invert);
+
+        let config = Config::new(args(&["-S", "--", "-v", "poem.txt"])).unwrap();
+        assert_eq!("-v", config.query);
+        assert!(!config.case_sensitive);
+        assert_eq!(vec!["poem.txt"], config.filenames);
+
+        assert!(Config::new(args(&["nobody", "--"])).is_err());
+    }
+
+    #[test]
+    fn smart_case_depends_on_the_query() {
+        let contents = "FOO\nfoo\nFoo";
+
+        let config = Config::new(args(&["-S", "foo", "poem.txt"])).unwrap();
+        assert!(!config.case_sensitive);
+        assert_eq!(
+            vec!["FOO", "foo", "Foo"],
+            search_case_insensitive(&config.query, contents)
+        );
+
+        let config = Config::new(args(&["--smart-case", "Foo", "poem.txt"])).unwrap();
+        assert!(config.case_sensitive);
+        assert_eq!(vec!["Foo"], search(&config.query, contents));
+
+        let config = Config::new(args(&["-S", "-e", "foo", "-e", "BAR", "poem.txt"])).unwrap();
+        assert!(config.case_sensitive);
+    }
+
+    #[test]
+    fn count_matches_agrees_with_search() {
+        let contents = "\
+Rust:
+safe, fast, productive.
+Pick three.
+Trust me.
+";
+        for query in ["rust", "Rust", "t", "", "nothing", "\n"] {
+            assert_eq!(search(query, contents).len(), count_matches(query, contents, false));
+            assert_eq!(
+                search_case_insensitive(query, contents).len(),
+                count_matches(query, contents, true)
             );
+        }
+        assert_eq!(1, count_matches("rust", contents, false));
+        assert_eq!(2, count_matches("rust", contents, true));
+        assert_eq!(0, count_matches("rust", "", true));
+    }
+
+    #[test]
+    fn dash_c_counts_like_count_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("poem.txt");
+        let contents = "Rust:\nsafe, fast, productive.\nPick three.\nTrust me.\nRUST\n";
+        fs::write(&path, contents).unwrap();
+        let path = path.to_str().unwrap();
+
+        // -S ignores case for a lowercase query
+        for (flags, query, expected) in [
+            (&[][..], "Rust", count_matches("Rust", contents, false)),
+            (&["-S"][..], "rust", count_matches("rust", contents, true)),
+            (&["-v"][..], "Rust", 5 - count_matches("Rust", contents, false)),
+        ] {
+            let args = args(&[&["-c"], flags, &[query, path]].concat());
+            let mut out = Vec::new();
+            run(Config::new(args).unwrap(), &mut out).unwrap();
+            assert_eq!(format!("{}\n", expected), String::from_utf8(out).unwrap());
+        }
+    }
+
+    #[test]
+    fn context_searches_stream_each_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("poem.txt");
+        let contents = "one\ntwo nobody\nthree\nfour\nfive\nsix nobody\r\nseven";
+        fs::write(&path, contents).unwrap();
+        let path = path.to_str().unwrap();
+
+        for flags in [&["-C", "1"][..], &["-B", "2", "-m", "1"], &["-A", "3", "-v"]] {
+            let config = Config::new(args(&[flags, &["nobody", path]].concat())).unwrap();
+            assert!(Searcher::new(&config).unwrap().streams());
+
+            let mut streamed = Vec::new();
+            let summary = run(config.clone(), &mut streamed).unwrap();
+            // the same search, but with the whole file read first
+            let mut buffered = Vec::new();
+            let config = Config { mmap: true, ..config };
+            assert!(!Searcher::new(&config).unwrap().streams());
+            assert_eq!(summary, run(config, &mut buffered).unwrap());
+            assert_eq!(
+                String::from_utf8(buffered).unwrap(),
+                String::from_utf8(streamed).unwrap(),
+                "{:?}",
+                flags
+            );
+        }
+    }
+
+    #[test]
+    fn streamed_binary_files_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("image.bin"), [b'a', b'\n', 0xFF, b'\n']).unwrap();
+        let dir = dir.path().to_str().unwrap();
+
+        let mut out = Vec::new();
+        let config = Config::new(args(&["-r", "-C", "1", "a", dir])).unwrap();
+        assert_eq!(0, run(config, &mut out).unwrap().total_matches);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn multiline_needs_regex() {
+        let config = Config::new(args(&["-E", "--multiline", "foo\nbar", "poem.txt"])).unwrap();
+        assert!(config.regex);
+        assert!(config.multiline);
+
+        assert!(Config::new(args(&["--multiline", "foo", "poem.txt"])).is_err());
+    }
+
+    #[test]
+    fn multiline_scan_matches_across_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("poem.txt");
+        fs::write(&path, "foo\nbar\nfoo\nbaz\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        let config = Config::new(args(&["-E", "--multiline", "foo\nbar", path])).unwrap();
+        assert_eq!(2, scanned(&config, &mut io::sink()).matches);
+
+        // line by line, no single line contains the newline
+        let config = Config::new(args(&["-E", "foo\nbar", path])).unwrap();
+        assert_eq!(0, scanned(&config, &mut io::sink()).matches);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn config_round_trips_through_toml() {
+        let config = Config::new(args(&[
+            "-C", "1", "-m", "3", "--replace", "somebody", "nobody", "poem.txt",
+        ]))
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".minigrep.toml");
+        fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+
+        assert_eq!(Ok(config), Config::from_toml(&path));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn bad_config_files_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".minigrep.toml");
+        assert!(matches!(Config::from_toml(&path), Err(ConfigError::File(_))));
+
+        fs::write(&path, "recursive = \"yes\"\n").unwrap();
+        assert!(matches!(Config::from_toml(&path), Err(ConfigError::File(_))));
+
+        fs::write(&path, "recursiv = true\n").unwrap();
+        assert!(matches!(Config::from_toml(&path), Err(ConfigError::File(_))));
+
+        fs::write(&path, "recursive = true\n").unwrap();
+        let config = Config::from_toml(&path).unwrap();
+        assert!(config.recursive);
+        assert!(config.case_sensitive);
+    }
+
+    #[test]
+    fn match_ranges_finds_every_occurrence() {
+        let line = "nobody, nobody, and nobody";
+        assert_eq!(vec![0..6, 8..14, 20..26], match_ranges("nobody", line));
+        assert!(match_ranges("somebody", line).is_empty());
+        assert!(match_ranges("", line).is_empty());
+    }
+
+    #[test]
+    fn match_ranges_at_the_very_end() {
+        let line = "How dreary to be somebody";
+        assert_eq!(vec![17..25], match_ranges("somebody", line));
+        assert_eq!("somebody", &line[17..25]);
+    }
+
+    #[test]
+    fn match_ranges_do_not_overlap() {
+        assert_eq!(vec![0..2, 2..4], match_ranges("aa", "aaaaa"));
+    }
+
+    #[test]
+    fn match_ranges_are_byte_offsets() {
+        let line = "¿Quién? ¿qué? ¿quién?";
+        let ranges = match_ranges("quién", line);
+        assert_eq!(vec![20..26], ranges);
+        for range in ranges {
+            assert_eq!("quién", &line[range]);
+        }
+        assert_eq!(vec![0..4, 4..8], match_ranges("😻", "😻😻"));
+    }
+
+    #[test]
+    fn case_insensitive_accents() {
+        let contents = "\
+ÉCOLE
+école
+ecole";
+        assert_eq!(vec!["ÉCOLE", "école"], search_case_insensitive("école", contents));
+        assert_eq!(vec!["ÉCOLE", "école"], search_case_insensitive("École", contents));
+    }
+
+    #[test]
+    fn case_insensitive_multi_char_lowercase() {
+        // ẞ lowercases to ß
+        let contents = "\
+GROẞ
+groß
+gross";
+        assert_eq!(vec!["GROẞ", "groß"], search_case_insensitive("groß", contents));
+        assert_eq!(vec!["GROẞ", "groß"], search_case_insensitive("GROẞ", contents));
+
+        // İ lowercases to i followed by a combining dot
+        assert_eq!("i\u{307}", "İ".to_lowercase());
+        assert_eq!(
+            vec!["İSTANBUL"],
+            search_case_insensitive("İstanbul", "İSTANBUL\nistanbul")
+        );
+    }
+
+    #[test]
+    fn case_insensitive_matches_lowercasing_every_line() {
+        fn naive<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
+            let query = query.to_lowercase();
+            contents
+                .lines()
+                .filter(|line| line.to_lowercase().contains(&query))
+                .collect()
+        }
+
+        let contents = "\
+Rust:
+safe, fast, productive.
+Pick three.
+Trust me.
+ÉCOLE and école
+GROẞ
+20 \u{212A}
+İSTANBUL
+istanbul
+
+[rust]";
+        for query in [
+            "rust", "RUST", "rUsT", "t.", "", " ", "me.", "école", "É", "ß", "k", "20 K",
+            "i", "İ", "i\u{307}", "[", "three.\n", "nothing",
+        ] {
+            assert_eq!(
+                naive(query, contents),
+                search_case_insensitive(query, contents),
+                "{:?}",
+                query
+            );
+        }
     }
 }
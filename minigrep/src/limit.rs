@@ -0,0 +1,113 @@
+use std::fmt;
+use std::io::{self, Write};
+
+/// A writer that lets through at most a given number of lines, for
+/// `--max-output-lines`.
+///
+/// Once the limit is reached, writing more fails with an error wrapping
+/// [`Truncated`], and [`LineLimit::truncated`] turns true.
+pub struct LineLimit<W: Write> {
+    out: W,
+    remaining: usize,
+    terminator: u8,
+    truncated: bool,
+}
+
+/// The error returned for output past the limit.
+#[derive(Debug)]
+pub struct Truncated;
+
+impl fmt::Display for Truncated {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "output truncated")
+    }
+}
+
+impl std::error::Error for Truncated {}
+
+impl<W: Write> LineLimit<W> {
+    /// Lets `max` lines ending in `terminator` through to `out`, or every
+    /// line when `max` is `None`.
+    pub fn new(out: W, max: Option<usize>, terminator: u8) -> LineLimit<W> {
+        LineLimit {
+            out,
+            remaining: max.unwrap_or(usize::MAX),
+            terminator,
+            truncated: false,
+        }
+    }
+
+    /// Whether some output was held back.
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl<W: Write> Write for LineLimit<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            self.truncated = true;
+            return Err(io::Error::other(Truncated));
+        }
+        // write up to the end of the last line that is still allowed
+        let mut end = buf.len();
+        let mut lines = 0;
+        for (i, &b) in buf.iter().enumerate() {
+            if b == self.terminator {
+                lines += 1;
+                if lines == self.remaining {
+                    end = i + 1;
+                    break;
+                }
+            }
+        }
+        self.out.write_all(&buf[..end])?;
+        self.remaining -= lines;
+        Ok(end)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stops_after_the_last_allowed_line() {
+        let mut limit = LineLimit::new(Vec::new(), Some(2), b'\n');
+        writeln!(limit, "I'm nobody!").unwrap();
+        assert!(!limit.truncated());
+
+        let e = limit
+            .write_all(b"Who are you?\nAre you nobody, too?\n")
+            .unwrap_err();
+        assert!(e.get_ref().unwrap().is::<Truncated>());
+        assert!(limit.truncated());
+        assert_eq!(b"I'm nobody!\nWho are you?\n", &limit.out[..]);
+    }
+
+    #[test]
+    fn exactly_the_limit_is_not_truncated() {
+        let mut limit = LineLimit::new(Vec::new(), Some(1), b'\0');
+        limit.write_all(b"one\0").unwrap();
+        limit.flush().unwrap();
+        assert!(!limit.truncated());
+        assert_eq!(b"one\0", &limit.out[..]);
+    }
+
+    #[test]
+    fn no_limit_lets_everything_through() {
+        let mut limit = LineLimit::new(Vec::new(), None, b'\n');
+        for _ in 0..1000 {
+            writeln!(limit, "nobody").unwrap();
+        }
+        assert!(!limit.truncated());
+        assert_eq!(7000, limit.out.len());
+    }
+}
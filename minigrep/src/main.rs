@@ -1,15 +1,59 @@
 use std::process;
 use std::env;
-use minigrep::Config;
+use std::io::{self, IsTerminal};
+use minigrep::{Config, ConfigError, ErrorMode, FileErrors};
 
 fn main(){
-    let config = Config::new(env::args()).unwrap_or_else(|err| {
-        eprintln!("Problem parsing arguments: {}", err);
-        process::exit(1);
-    });
+    let mut config = defaults()
+        .and_then(|defaults| Config::with_defaults(defaults, env::args()))
+        .unwrap_or_else(|err| {
+            eprintln!("Problem parsing arguments: {}", err);
+            process::exit(1);
+        });
 
-    if let Err(e) = minigrep::run(config) {
+    if config.query_from_stdin {
+        if let Err(err) = config.read_query(io::stdin().lock()) {
+            eprintln!("Problem parsing arguments: {}", err);
+            process::exit(1);
+        }
+    }
+
+    // several files are searched past the ones that can't be read, unless
+    // --stop-on-error is given
+    if config.filenames.len() == 1 || config.error_mode == ErrorMode::Stop {
+        if let Err(err) = config.validate() {
+            eprintln!("Problem parsing arguments: {}", err);
+            process::exit(1);
+        }
+    }
+
+    // only color the output when someone is looking at it, and like grep,
+    // flush a terminal after every line
+    let stdout = io::stdout();
+    if stdout.is_terminal() {
+        config.color = true;
+        config.line_buffered = true;
+    }
+
+    if let Err(e) = minigrep::run(config, &mut stdout.lock()) {
         eprintln!("Application error: {}", e);
-        process::exit(1);
+        // like grep, 2 means some files couldn't be read
+        process::exit(if e.is::<FileErrors>() { 2 } else { 1 });
     }
 }
+
+// default options from .minigrep.toml in the current directory, if there is one
+#[cfg(feature = "serde")]
+fn defaults() -> Result<Config, ConfigError> {
+    let path = std::path::Path::new(".minigrep.toml");
+    if path.exists() {
+        Config::from_toml(path)
+    } else {
+        Ok(Config::default())
+    }
+}
+
+#[cfg(not(feature = "serde"))]
+fn defaults() -> Result<Config, ConfigError> {
+    Ok(Config::default())
+}
@@ -0,0 +1,177 @@
+use crate::patterns::{self, MultiMatcher};
+use crate::Config;
+use regex::Regex;
+use std::io;
+use std::ops::Range;
+
+/// Decides which lines match, and where.
+///
+/// One is built from the [`Config`] with [`from_config`] before the search
+/// starts and then used for every line, so the search itself doesn't need
+/// to know whether it looks for literals, a regex or whole words.
+pub trait Matcher: Send + Sync {
+    /// Whether `line` has a match anywhere.
+    fn is_match(&self, line: &str) -> bool;
+
+    /// The byte range of every match in `line`, left to right and without
+    /// overlaps. The ranges fall on character boundaries.
+    fn find_ranges(&self, line: &str) -> Vec<Range<usize>>;
+}
+
+/// Builds the matcher `config` asks for: a [`RegexMatcher`] with `--regex`,
+/// a [`SubstringMatcher`] otherwise, wrapped in a [`WholeWordMatcher`] with
+/// `-w`.
+pub fn from_config(config: &Config) -> io::Result<Box<dyn Matcher>> {
+    let matcher: Box<dyn Matcher> = if config.regex {
+        Box::new(RegexMatcher::new(&config.patterns, config.case_sensitive)?)
+    } else {
+        Box::new(SubstringMatcher::new(
+            &config.patterns,
+            config.case_sensitive,
+        )?)
+    };
+    if config.word {
+        return Ok(Box::new(WholeWordMatcher::new(matcher)));
+    }
+    Ok(matcher)
+}
+
+/// Looks for any of several literal patterns.
+///
+/// The patterns share one Aho-Corasick automaton, see [`MultiMatcher`].
+pub struct SubstringMatcher {
+    patterns: MultiMatcher,
+}
+
+impl SubstringMatcher {
+    pub fn new(patterns: &[String], case_sensitive: bool) -> io::Result<SubstringMatcher> {
+        Ok(SubstringMatcher {
+            patterns: MultiMatcher::new(patterns, case_sensitive)?,
+        })
+    }
+}
+
+impl Matcher for SubstringMatcher {
+    fn is_match(&self, line: &str) -> bool {
+        self.patterns.is_match(line)
+    }
+
+    fn find_ranges(&self, line: &str) -> Vec<Range<usize>> {
+        self.patterns.find_ranges(line)
+    }
+}
+
+/// Looks for any of several regexes, for `--regex`.
+pub struct RegexMatcher {
+    regex: Regex,
+}
+
+impl RegexMatcher {
+    pub fn new(patterns: &[String], case_sensitive: bool) -> io::Result<RegexMatcher> {
+        let regex = patterns::build_regex(patterns, case_sensitive, false)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        Ok(RegexMatcher { regex })
+    }
+}
+
+impl Matcher for RegexMatcher {
+    fn is_match(&self, line: &str) -> bool {
+        self.regex.is_match(line)
+    }
+
+    fn find_ranges(&self, line: &str) -> Vec<Range<usize>> {
+        self.regex.find_iter(line).map(|m| m.range()).collect()
+    }
+}
+
+/// Keeps only the matches of another matcher that are whole words, for
+/// `-w`.
+///
+/// A match is a whole word when neither the character before it nor the
+/// one after it is a letter, a digit or `_`.
+pub struct WholeWordMatcher {
+    inner: Box<dyn Matcher>,
+}
+
+impl WholeWordMatcher {
+    pub fn new(inner: Box<dyn Matcher>) -> WholeWordMatcher {
+        WholeWordMatcher { inner }
+    }
+}
+
+impl Matcher for WholeWordMatcher {
+    fn is_match(&self, line: &str) -> bool {
+        !self.find_ranges(line).is_empty()
+    }
+
+    fn find_ranges(&self, line: &str) -> Vec<Range<usize>> {
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+        let mut ranges = self.inner.find_ranges(line);
+        ranges.retain(|range| {
+            !line[..range.start].ends_with(is_word) && !line[range.end..].starts_with(is_word)
+        });
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn substring_matcher_finds_every_pattern() {
+        let matcher: &dyn Matcher =
+            &SubstringMatcher::new(&patterns(&["nobody", "you"]), true).unwrap();
+        assert!(matcher.is_match("Are you nobody, too?"));
+        assert!(!matcher.is_match("Then there's a pair of us"));
+        assert_eq!(
+            vec![4..7, 8..14],
+            matcher.find_ranges("Are you nobody, too?")
+        );
+    }
+
+    #[test]
+    fn case_insensitive_ranges_point_into_the_original_line() {
+        let matcher: &dyn Matcher = &SubstringMatcher::new(&patterns(&["émile"]), false).unwrap();
+        let line = "Ὀδυσσεύς ÉMILE";
+        assert!(matcher.is_match(line));
+        let ranges = matcher.find_ranges(line);
+        assert_eq!(
+            vec!["ÉMILE"],
+            ranges.into_iter().map(|r| &line[r]).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn overlapping_patterns_prefer_the_leftmost() {
+        let matcher: &dyn Matcher =
+            &SubstringMatcher::new(&patterns(&["bc", "abcd"]), true).unwrap();
+        assert_eq!(vec![0..4], matcher.find_ranges("abcd"));
+    }
+
+    #[test]
+    fn regex_matcher_finds_every_match() {
+        let matcher: &dyn Matcher = &RegexMatcher::new(&patterns(&[r"\d+"]), true).unwrap();
+        assert!(matcher.is_match("Hello, 2024"));
+        assert!(!matcher.is_match("Hello, world"));
+        assert_eq!(vec![0..2, 5..8], matcher.find_ranges("12 + 345"));
+        assert!(RegexMatcher::new(&patterns(&["("]), true).is_err());
+    }
+
+    #[test]
+    fn whole_word_matcher_skips_parts_of_words() {
+        let inner = SubstringMatcher::new(&patterns(&["body"]), true).unwrap();
+        let matcher: &dyn Matcher = &WholeWordMatcher::new(Box::new(inner));
+        assert!(!matcher.is_match("I'm nobody! Who are you?"));
+        assert!(matcher.is_match("body and soul"));
+        assert_eq!(vec![11..15], matcher.find_ranges("somebody, (body)"));
+
+        let inner = RegexMatcher::new(&patterns(&["fo+"]), true).unwrap();
+        let matcher: &dyn Matcher = &WholeWordMatcher::new(Box::new(inner));
+        assert_eq!(vec![5..8], matcher.find_ranges("foo_ foo fooé"));
+    }
+}
@@ -0,0 +1,91 @@
+use memmap2::Mmap;
+use std::fs::{self, File};
+use std::io;
+use std::ops::Deref;
+use std::path::Path;
+use std::str;
+
+/// The text of a file, either read into memory or mapped with `--mmap`.
+///
+/// Derefs to `str` either way, so the search doesn't care which it got.
+pub enum Contents {
+    Read(String),
+    /// A mapping whose bytes were already checked to be valid UTF-8.
+    Mapped(Mmap),
+}
+
+impl Deref for Contents {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            Contents::Read(contents) => contents,
+            // SAFETY: `map_file` only returns mappings that passed `from_utf8`
+            Contents::Mapped(map) => unsafe { str::from_utf8_unchecked(map) },
+        }
+    }
+}
+
+/// Reads `path` like `fs::read_to_string`, or maps it into memory with
+/// `mmap`.
+///
+/// A mapping is checked for UTF-8 in place instead of being copied into a
+/// `String`, which saves a lot for multi-gigabyte files. Files that can't be
+/// mapped, like pipes and other special files, are read as usual.
+pub fn read(path: &Path, mmap: bool) -> io::Result<Contents> {
+    if mmap {
+        if let Some(contents) = map_file(path)? {
+            return Ok(contents);
+        }
+    }
+    fs::read_to_string(path).map(Contents::Read)
+}
+
+// Returns `None` when the file exists but can't be mapped.
+fn map_file(path: &Path) -> io::Result<Option<Contents>> {
+    let file = File::open(path)?;
+    // SAFETY: the mapping is only valid while nobody else truncates the
+    // file; like grep, we accept that risk for the files we search
+    let map = match unsafe { Mmap::map(&file) } {
+        Ok(map) => map,
+        Err(_) => return Ok(None),
+    };
+    match str::from_utf8(&map) {
+        Ok(_) => Ok(Some(Contents::Mapped(map))),
+        Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mapped_and_read_contents_match() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("poem.txt");
+        fs::write(&path, "I'm nobody! Who are you?\nAre you nobody, too?\n").unwrap();
+
+        let mapped = read(&path, true).unwrap();
+        assert!(matches!(mapped, Contents::Mapped(_)));
+        assert_eq!(&*read(&path, false).unwrap(), &*mapped);
+    }
+
+    #[test]
+    fn mapped_binary_files_are_invalid_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("image.bin");
+        fs::write(&path, [b'a', 0xFF, 0xFE, b'\n']).unwrap();
+
+        let e = read(&path, true).err().unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, e.kind());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn special_files_fall_back_to_reading() {
+        let contents = read(Path::new("/dev/null"), true).unwrap();
+        assert!(matches!(contents, Contents::Read(_)));
+        assert_eq!("", &*contents);
+    }
+}
@@ -0,0 +1,232 @@
+use aho_corasick::{AhoCorasick, MatchKind};
+use regex::{Regex, RegexBuilder};
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+/// Matches a line against many literal patterns at once.
+///
+/// All patterns are compiled into a single Aho-Corasick automaton, so each
+/// line is scanned once no matter how many patterns there are, instead of
+/// once per pattern with `contains`.
+pub struct MultiMatcher {
+    automaton: AhoCorasick,
+    case_sensitive: bool,
+    // without case sensitivity, whether the patterns are all ASCII, so that
+    // the automaton ignores their case itself
+    ascii: bool,
+}
+
+impl MultiMatcher {
+    /// Builds the automaton. When `case_sensitive` is false and the patterns
+    /// are all ASCII, the automaton ignores ASCII case and lines are searched
+    /// as they are. Other patterns are lowercased here, once, and each line
+    /// is lowercased before matching, like [`crate::search_case_insensitive`]
+    /// does.
+    pub fn new(patterns: &[String], case_sensitive: bool) -> Result<MultiMatcher, io::Error> {
+        let ascii = !case_sensitive && patterns.iter().all(|p| p.is_ascii());
+        // where two patterns overlap, the one starting first wins
+        let mut builder = AhoCorasick::builder();
+        builder
+            .match_kind(MatchKind::LeftmostFirst)
+            .ascii_case_insensitive(ascii);
+        let automaton = if case_sensitive || ascii {
+            builder.build(patterns)
+        } else {
+            builder.build(patterns.iter().map(|p| p.to_lowercase()))
+        };
+        let automaton = automaton.map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        Ok(MultiMatcher {
+            automaton,
+            case_sensitive,
+            ascii,
+        })
+    }
+
+    // Whether the automaton can search `line` as it is. With ASCII patterns
+    // that is any line without the only characters that lowercase to ASCII:
+    // the Kelvin sign, to `k`, and `İ`, to `i` and a combining dot.
+    fn searches_as_is(&self, line: &str) -> bool {
+        self.case_sensitive || (self.ascii && !line.contains(['\u{212A}', '\u{130}']))
+    }
+
+    /// Whether `line` contains any of the patterns.
+    pub fn is_match(&self, line: &str) -> bool {
+        if self.searches_as_is(line) {
+            self.automaton.is_match(line)
+        } else {
+            self.automaton.is_match(&line.to_lowercase())
+        }
+    }
+
+    /// The byte range of every match in `line`, left to right and without
+    /// overlaps.
+    ///
+    /// Without case sensitivity and with patterns that aren't all ASCII, the
+    /// search runs over the lowercased line, and the ranges are mapped back
+    /// to where those characters are in `line`.
+    pub fn find_ranges(&self, line: &str) -> Vec<Range<usize>> {
+        if self.searches_as_is(line) {
+            return self.automaton.find_iter(line).map(|m| m.range()).collect();
+        }
+
+        // lowercasing can change how many bytes a character takes, so
+        // remember where every lowercased byte came from
+        let mut offsets = Vec::with_capacity(line.len() + 1);
+        for (start, c) in line.char_indices() {
+            let len: usize = c.to_lowercase().map(char::len_utf8).sum();
+            offsets.extend(std::iter::repeat_n(start, len));
+        }
+        offsets.push(line.len());
+
+        self.automaton
+            .find_iter(&line.to_lowercase())
+            .map(|m| offsets[m.start()]..offsets[m.end()])
+            .collect()
+    }
+}
+
+/// Compiles the patterns for `--regex` into one regex that matches any of
+/// them.
+///
+/// With `multiline` the regex is meant to run over a whole file: `.` also
+/// matches a newline, and `^` and `$` match at the start and end of every
+/// line.
+pub fn build_regex(
+    patterns: &[String],
+    case_sensitive: bool,
+    multiline: bool,
+) -> Result<Regex, regex::Error> {
+    let pattern = match patterns {
+        [pattern] => pattern.clone(),
+        _ => patterns
+            .iter()
+            .map(|pattern| format!("(?:{})", pattern))
+            .collect::<Vec<_>>()
+            .join("|"),
+    };
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!case_sensitive)
+        .multi_line(multiline)
+        .dot_matches_new_line(multiline)
+        .build()
+}
+
+/// Reads one pattern per line, skipping empty lines.
+pub fn read_patterns(path: &Path) -> io::Result<Vec<String>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn regex_matches_any_pattern() {
+        let regex = build_regex(&strings(&["^nob.dy", r"\d+$"]), true, false).unwrap();
+        assert!(regex.is_match("nobody here"));
+        assert!(regex.is_match("room 101"));
+        assert!(!regex.is_match("Nobody, 101 times"));
+
+        let regex = build_regex(&strings(&["^nob.dy"]), false, false).unwrap();
+        assert!(regex.is_match("NOBODY"));
+
+        assert!(build_regex(&strings(&["(unclosed"]), true, false).is_err());
+    }
+
+    fn naive_is_match(patterns: &[String], line: &str) -> bool {
+        patterns
+            .iter()
+            .any(|pattern| line.contains(pattern.as_str()))
+    }
+
+    #[test]
+    fn agrees_with_looping_over_contains() {
+        let patterns: Vec<String> = ["nobody", "frog", "bog", "you"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let matcher = MultiMatcher::new(&patterns, true).unwrap();
+
+        for line in include_str!("../poem.txt").lines() {
+            assert_eq!(
+                naive_is_match(&patterns, line),
+                matcher.is_match(line),
+                "{}",
+                line
+            );
+        }
+        assert!(matcher.is_match("How public, like a frog"));
+        assert!(!matcher.is_match("How dreary to be somebody!"));
+    }
+
+    #[test]
+    fn agrees_with_contains_for_many_patterns() {
+        let patterns: Vec<String> = (0..1000).map(|i| format!("word{}x", i)).collect();
+        let matcher = MultiMatcher::new(&patterns, true).unwrap();
+
+        for line in [
+            "has word999x in it",
+            "word1000x is not a pattern",
+            "",
+            "word5x",
+        ] {
+            assert_eq!(naive_is_match(&patterns, line), matcher.is_match(line));
+        }
+    }
+
+    #[test]
+    fn case_insensitive_patterns() {
+        let patterns = vec![String::from("NOBODY"), String::from("École")];
+        let matcher = MultiMatcher::new(&patterns, false).unwrap();
+
+        assert!(matcher.is_match("I'm nobody! Who are you?"));
+        assert!(matcher.is_match("ÉCOLE"));
+        assert!(!matcher.is_match("somebody"));
+    }
+
+    #[test]
+    fn ascii_patterns_ignore_case_like_lowercasing() {
+        let matcher = MultiMatcher::new(&strings(&["nobody", "ok"]), false).unwrap();
+        assert!(matcher.ascii);
+
+        for line in [
+            "I'm NOBODY!",
+            "Ça va, Nobody?",
+            "\u{212A}",
+            "O\u{212A}",
+            "İt's nobody",
+        ] {
+            let lowercase = line.to_lowercase();
+            assert_eq!(
+                naive_is_match(&strings(&["nobody", "ok"]), &lowercase),
+                matcher.is_match(line),
+                "{}",
+                line
+            );
+        }
+        assert_eq!(vec![8..14], matcher.find_ranges("Ça va, NoBody?"));
+        assert_eq!(vec![0..4], matcher.find_ranges("O\u{212A} then"));
+        assert_eq!(vec![6..12], matcher.find_ranges("İt's nobody"));
+    }
+
+    #[test]
+    fn reads_patterns_from_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("patterns.txt");
+        fs::write(&path, "nobody\n\nfrog\n").unwrap();
+
+        assert_eq!(vec!["nobody", "frog"], read_patterns(&path).unwrap());
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// A single `scanned N/M files, K matches` line that is redrawn in place.
+///
+/// The line is overwritten with `\r` instead of printing new lines, and is
+/// redrawn at most once per `interval`. A disabled reporter writes nothing.
+pub struct Progress<W: Write> {
+    out: Option<W>,
+    total: usize,
+    interval: Duration,
+    last_draw: Option<Instant>,
+    width: usize,
+}
+
+impl Progress<io::Stderr> {
+    /// Reports on stderr every 500ms, but only when stderr is a terminal.
+    pub fn stderr(enabled: bool, total: usize) -> Progress<io::Stderr> {
+        use std::io::IsTerminal;
+
+        let out = if enabled && io::stderr().is_terminal() {
+            Some(io::stderr())
+        } else {
+            None
+        };
+        Progress::new(out, total, Duration::from_millis(500))
+    }
+}
+
+impl<W: Write> Progress<W> {
+    pub fn new(out: Option<W>, total: usize, interval: Duration) -> Progress<W> {
+        Progress {
+            out,
+            total,
+            interval,
+            last_draw: None,
+            width: 0,
+        }
+    }
+
+    /// Redraws the line if the interval has passed since the last redraw.
+    pub fn update(&mut self, scanned: usize, matches: usize) {
+        let out = match self.out.as_mut() {
+            Some(out) => out,
+            None => return,
+        };
+        if let Some(last) = self.last_draw {
+            if last.elapsed() < self.interval {
+                return;
+            }
+        }
+
+        let line = format!(
+            "scanned {}/{} files, {} matches",
+            scanned, self.total, matches
+        );
+        // pad with spaces so a shorter line fully covers the previous one
+        let _ = write!(out, "\r{:<width$}", line, width = self.width);
+        let _ = out.flush();
+        self.width = self.width.max(line.len());
+        self.last_draw = Some(Instant::now());
+    }
+
+    /// Wipes the line, e.g. before printing results or when the scan is done.
+    pub fn clear(&mut self) {
+        if let Some(out) = self.out.as_mut() {
+            if self.width > 0 {
+                let _ = write!(out, "\r{}\r", " ".repeat(self.width));
+                let _ = out.flush();
+                self.width = 0;
+                // draw again on the next update instead of waiting
+                self.last_draw = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overwrites_the_line_instead_of_adding_new_ones() {
+        let mut progress = Progress::new(Some(Vec::new()), 3, Duration::ZERO);
+        progress.update(1, 12);
+        progress.update(2, 3);
+        progress.clear();
+
+        let out = String::from_utf8(progress.out.unwrap()).unwrap();
+        assert_eq!(
+            format!(
+                "\rscanned 1/3 files, 12 matches\rscanned 2/3 files, 3 matches \r{}\r",
+                " ".repeat(29)
+            ),
+            out
+        );
+        assert!(!out.contains('\n'));
+    }
+
+    #[test]
+    fn waits_for_the_interval_between_redraws() {
+        let mut progress = Progress::new(Some(Vec::new()), 3, Duration::from_secs(60));
+        progress.update(1, 0);
+        progress.update(2, 0);
+        progress.update(3, 0);
+
+        let out = String::from_utf8(progress.out.unwrap()).unwrap();
+        assert_eq!("\rscanned 1/3 files, 0 matches", out);
+    }
+
+    #[test]
+    fn disabled_progress_writes_nothing() {
+        let mut progress: Progress<Vec<u8>> = Progress::new(None, 3, Duration::ZERO);
+        progress.update(1, 1);
+        progress.clear();
+        assert!(progress.out.is_none());
+    }
+}
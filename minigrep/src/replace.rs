@@ -0,0 +1,310 @@
+use crate::color::{self, Color};
+use regex::Regex;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// What a replacement looks for: a literal query, or a regex in `--regex`
+/// mode.
+#[derive(Debug, Clone, Copy)]
+pub enum Pattern<'a> {
+    Literal(&'a str),
+    Regex(&'a Regex),
+}
+
+impl<'a> From<&'a str> for Pattern<'a> {
+    fn from(query: &'a str) -> Pattern<'a> {
+        Pattern::Literal(query)
+    }
+}
+
+impl<'a> From<&'a Regex> for Pattern<'a> {
+    fn from(regex: &'a Regex) -> Pattern<'a> {
+        Pattern::Regex(regex)
+    }
+}
+
+/// How many replacements were made, and on how many lines.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReplaceCount {
+    pub replacements: usize,
+    pub lines: usize,
+}
+
+impl ReplaceCount {
+    /// Adds the replacements made on one line; lines without any aren't
+    /// counted.
+    pub fn add_line(&mut self, replacements: usize) {
+        if replacements > 0 {
+            self.replacements += replacements;
+            self.lines += 1;
+        }
+    }
+}
+
+/// Replaces every occurrence of `pattern` in a single line with
+/// `replacement`, returning the new line and how many replacements were
+/// made.
+///
+/// For a regex, `replacement` is a template like in `sed`: `$1` or `${1}`
+/// is the first capture group, `${name}` a named group, and `$$` a literal
+/// `$`. Group names run as far as letters, digits and `_` go, so write
+/// `${1}a` rather than `$1a`.
+pub fn apply_replacement<'p>(
+    pattern: impl Into<Pattern<'p>>,
+    line: &str,
+    replacement: &str,
+) -> (String, usize) {
+    let pattern = pattern.into();
+    let count = match pattern {
+        Pattern::Literal(query) => line.matches(query).count(),
+        Pattern::Regex(regex) => regex.find_iter(line).count(),
+    };
+    if count == 0 {
+        return (line.to_string(), 0);
+    }
+
+    let replaced = match pattern {
+        Pattern::Literal(query) => line.replace(query, replacement),
+        Pattern::Regex(regex) => regex.replace_all(line, replacement).into_owned(),
+    };
+    (replaced, count)
+}
+
+/// Applies the replacement line by line, keeping the original line endings.
+pub fn replace_all<'p>(
+    pattern: impl Into<Pattern<'p>>,
+    contents: &str,
+    replacement: &str,
+) -> (String, ReplaceCount) {
+    let pattern = pattern.into();
+    let mut count = ReplaceCount::default();
+    let replaced = contents
+        .split_inclusive('\n')
+        .map(|line| {
+            let (replaced, replacements) = apply_replacement(pattern, line, replacement);
+            count.add_line(replacements);
+            replaced
+        })
+        .collect();
+    (replaced, count)
+}
+
+/// Writes a diff-style preview of the replacement without touching any file.
+///
+/// Every line that would change is shown as `- old line` followed by
+/// `+ new line`; lines without a match are left out. With `color`, the old
+/// lines are red and the new ones green.
+pub fn write_preview<'p, W: Write + ?Sized>(
+    out: &mut W,
+    pattern: impl Into<Pattern<'p>>,
+    contents: &str,
+    replacement: &str,
+    color: bool,
+) -> io::Result<ReplaceCount> {
+    let pattern = pattern.into();
+    let mut count = ReplaceCount::default();
+    for line in contents.lines() {
+        let (replaced, replacements) = apply_replacement(pattern, line, replacement);
+        count.add_line(replacements);
+        if replaced == line {
+            continue;
+        }
+        let removed = format!("- {}", line);
+        let added = format!("+ {}", replaced);
+        if color {
+            writeln!(out, "{}", color::colorize(&removed, Color::Red))?;
+            writeln!(out, "{}", color::colorize(&added, Color::Green))?;
+        } else {
+            writeln!(out, "{}", removed)?;
+            writeln!(out, "{}", added)?;
+        }
+    }
+    Ok(count)
+}
+
+/// Writes `contents` back to `path` without ever leaving a half-written file.
+///
+/// When `backup` is set, the original file is first copied to a sidecar file
+/// named `path` + `backup` (e.g. `poem.txt.bak`). The new contents are written
+/// to a temporary file in the same directory and then renamed over the
+/// original, so a crash in the middle leaves the original untouched.
+pub fn write_in_place(path: &Path, contents: &str, backup: Option<&str>) -> io::Result<()> {
+    if let Some(suffix) = backup {
+        fs::copy(path, sidecar_path(path, suffix))?;
+    }
+
+    let temp = sidecar_path(path, ".minigrep.tmp");
+    if let Err(e) = write_and_rename(path, &temp, contents) {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// Writes `contents` to `path`, creating the directories it's in first.
+///
+/// This is how `--output-dir` writes the replaced copy of a file, leaving the
+/// original alone.
+pub fn write_copy(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, contents)
+}
+
+fn write_and_rename(path: &Path, temp: &Path, contents: &str) -> io::Result<()> {
+    fs::write(temp, contents)?;
+    // keep the permissions of the original file
+    fs::set_permissions(temp, fs::metadata(path)?.permissions())?;
+    fs::rename(temp, path)
+}
+
+fn sidecar_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_every_occurrence_in_a_line() {
+        assert_eq!(
+            (String::from("Are you somebody, too, somebody?"), 2),
+            apply_replacement("nobody", "Are you nobody, too, nobody?", "somebody")
+        );
+        assert_eq!(
+            (String::from("Who are you?"), 0),
+            apply_replacement("nobody", "Who are you?", "somebody")
+        );
+    }
+
+    #[test]
+    fn regex_replacement_uses_capture_groups() {
+        let regex = Regex::new(r"(\w+)@(\w+)").unwrap();
+        assert_eq!(
+            (String::from("mail example.jane or example.john"), 2),
+            apply_replacement(&regex, "mail jane@example or john@example", "$2.$1")
+        );
+        assert_eq!(
+            (String::from("jane costs $example"), 1),
+            apply_replacement(&regex, "jane@example", "$1 costs $$$2")
+        );
+        assert_eq!(
+            (String::from("jane_at_example"), 1),
+            apply_replacement(&regex, "jane@example", "${1}_at_$2")
+        );
+    }
+
+    #[test]
+    fn replace_all_keeps_line_endings() {
+        let contents = "\
+Rust:
+safe, fast, productive.
+Trust me.";
+        assert_eq!(
+            "Go:\nsafe, fast, productive.\nTrust me.",
+            replace_all("Rust", contents, "Go").0
+        );
+        assert_eq!("a\r\nb\n", replace_all("x", "a\r\nb\n", "y").0);
+    }
+
+    #[test]
+    fn replace_all_counts_replacements_and_lines() {
+        let contents = "\
+nobody, nobody, nobody!
+Who are you?
+Are you nobody, too?
+";
+        let (_, count) = replace_all("nobody", contents, "somebody");
+        assert_eq!(
+            ReplaceCount {
+                replacements: 4,
+                lines: 2
+            },
+            count
+        );
+
+        let mut out = Vec::new();
+        assert_eq!(
+            count,
+            write_preview(&mut out, "nobody", contents, "somebody", false).unwrap()
+        );
+    }
+
+    #[test]
+    fn preview_shows_only_changed_lines() {
+        let contents = "\
+I'm nobody! Who are you?
+Then there's a pair of us - don't tell!
+Are you nobody, too?";
+        let mut out = Vec::new();
+        write_preview(&mut out, "nobody", contents, "somebody", false).unwrap();
+        assert_eq!(
+            "\
+- I'm nobody! Who are you?
++ I'm somebody! Who are you?
+- Are you nobody, too?
++ Are you somebody, too?
+",
+            String::from_utf8(out).unwrap()
+        );
+    }
+
+    #[test]
+    fn colored_preview() {
+        let mut out = Vec::new();
+        write_preview(
+            &mut out,
+            "nobody",
+            "I'm nobody!\nWho are you?",
+            "somebody",
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            "\x1b[31m- I'm nobody!\x1b[0m\n\x1b[32m+ I'm somebody!\x1b[0m\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+
+    #[test]
+    fn in_place_rewrites_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("poem.txt");
+        fs::write(&path, "I'm nobody!\nWho are you?\n").unwrap();
+
+        write_in_place(&path, "I'm somebody!\nWho are you?\n", None).unwrap();
+
+        assert_eq!(
+            "I'm somebody!\nWho are you?\n",
+            fs::read_to_string(&path).unwrap()
+        );
+        // only the rewritten file is left behind
+        assert_eq!(1, fs::read_dir(dir.path()).unwrap().count());
+    }
+
+    #[test]
+    fn in_place_backup_matches_the_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("poem.txt");
+        let original = "I'm nobody!\nWho are you?\n";
+        fs::write(&path, original).unwrap();
+
+        let (replaced, _) = replace_all("nobody", original, "somebody");
+        write_in_place(&path, &replaced, Some(".bak")).unwrap();
+
+        assert_eq!(
+            "I'm somebody!\nWho are you?\n",
+            fs::read_to_string(&path).unwrap()
+        );
+        assert_eq!(
+            original,
+            fs::read_to_string(dir.path().join("poem.txt.bak")).unwrap()
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor};
+use std::path::PathBuf;
+
+/// Something to search that has a name and text, like a file, for
+/// [`crate::run_sources`].
+///
+/// This lets minigrep search text that isn't on disk, like a string in
+/// memory or a stream from elsewhere, by implementing `Source` for it.
+pub trait Source {
+    /// Printed in front of the matches, where a file's path would be.
+    fn name(&self) -> &str;
+
+    /// Opens the text for reading. It is read to the end once per search.
+    fn read(&self) -> io::Result<Box<dyn BufRead>>;
+}
+
+/// A file on disk, named by its path.
+pub struct FileSource {
+    path: PathBuf,
+    name: String,
+}
+
+impl FileSource {
+    pub fn new(path: impl Into<PathBuf>) -> FileSource {
+        let path = path.into();
+        FileSource {
+            name: path.to_string_lossy().into_owned(),
+            path,
+        }
+    }
+}
+
+impl Source for FileSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn read(&self) -> io::Result<Box<dyn BufRead>> {
+        Ok(Box::new(BufReader::new(File::open(&self.path)?)))
+    }
+}
+
+/// Text held in memory, under a name of its choosing.
+pub struct StringSource {
+    name: String,
+    contents: String,
+}
+
+impl StringSource {
+    pub fn new(name: &str, contents: &str) -> StringSource {
+        StringSource {
+            name: name.to_string(),
+            contents: contents.to_string(),
+        }
+    }
+}
+
+impl Source for StringSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn read(&self) -> io::Result<Box<dyn BufRead>> {
+        Ok(Box::new(Cursor::new(self.contents.clone().into_bytes())))
+    }
+}
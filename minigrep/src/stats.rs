@@ -0,0 +1,157 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Totals collected while scanning, printed by `--stats` and `--summary`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ScanStats {
+    pub bytes: u64,
+    pub lines: u64,
+    pub matches: u64,
+    /// Files that were searched, leaving out skipped binary files.
+    pub files: u64,
+    /// Files with at least one match in their name or contents.
+    pub files_with_matches: u64,
+    /// Files that couldn't be read.
+    pub errors: u64,
+    /// Replacements made with `--replace`, and the lines they were on.
+    pub replacements: u64,
+    pub replaced_lines: u64,
+    pub elapsed: Duration,
+    /// The matches in every searched file, only kept for `--histogram`.
+    pub per_file: Vec<(PathBuf, u64)>,
+}
+
+/// The totals [`crate::run`] returns, for callers that want the numbers
+/// without parsing the output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    /// Files with at least one match in their name or contents.
+    pub files_matched: usize,
+    pub total_matches: usize,
+}
+
+impl ScanStats {
+    /// Adds one scanned file. This is called once per file, not per line,
+    /// so it costs nothing inside the search loop itself.
+    pub fn add_file(&mut self, contents: &str, matches: usize) {
+        self.bytes += contents.len() as u64;
+        self.lines += contents.lines().count() as u64;
+        self.matches += matches as u64;
+    }
+
+    /// Counts one searched file, whether or not it matched.
+    pub fn count_file(&mut self, matched: bool) {
+        self.files += 1;
+        if matched {
+            self.files_with_matches += 1;
+        }
+    }
+
+    /// Adds the totals of another scan, like one of the files searched by
+    /// another thread. `elapsed` is left alone.
+    pub fn add(&mut self, other: &ScanStats) {
+        self.bytes += other.bytes;
+        self.lines += other.lines;
+        self.matches += other.matches;
+        self.files += other.files;
+        self.files_with_matches += other.files_with_matches;
+        self.errors += other.errors;
+        self.replacements += other.replacements;
+        self.replaced_lines += other.replaced_lines;
+        self.per_file.extend(other.per_file.iter().cloned());
+    }
+
+    /// The totals returned by [`crate::run`].
+    pub fn to_summary(&self) -> Summary {
+        Summary {
+            files_matched: self.files_with_matches as usize,
+            total_matches: self.matches as usize,
+        }
+    }
+
+    /// The line printed by `--summary`.
+    pub fn summary(&self) -> String {
+        format!(
+            "{} matches in {} files (scanned {} files)",
+            self.matches, self.files_with_matches, self.files
+        )
+    }
+}
+
+impl fmt::Display for ScanStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} matches, {} lines, {} bytes scanned in {:.3}s",
+            self.matches,
+            self.lines,
+            self.bytes,
+            self.elapsed.as_secs_f64()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_up_every_file() {
+        let mut stats = ScanStats::default();
+        stats.add_file("I'm nobody! Who are you?\nAre you nobody, too?\n", 2);
+        stats.add_file("no newline at the end", 0);
+        stats.add_file("", 0);
+
+        assert_eq!(67, stats.bytes);
+        assert_eq!(3, stats.lines);
+        assert_eq!(2, stats.matches);
+    }
+
+    #[test]
+    fn display() {
+        let stats = ScanStats {
+            bytes: 10,
+            lines: 2,
+            matches: 1,
+            files: 3,
+            files_with_matches: 1,
+            errors: 0,
+            replacements: 0,
+            replaced_lines: 0,
+            elapsed: Duration::from_millis(1500),
+            per_file: Vec::new(),
+        };
+        assert_eq!(
+            "1 matches, 2 lines, 10 bytes scanned in 1.500s",
+            stats.to_string()
+        );
+        assert_eq!("1 matches in 1 files (scanned 3 files)", stats.summary());
+    }
+
+    #[test]
+    fn adds_other_totals() {
+        let mut file = ScanStats::default();
+        file.add_file("I'm nobody! Who are you?\n", 1);
+        file.count_file(true);
+
+        let mut totals = ScanStats::default();
+        totals.add(&file);
+        totals.add(&file);
+        assert_eq!(50, totals.bytes);
+        assert_eq!(2, totals.lines);
+        assert_eq!(2, totals.matches);
+        assert_eq!(2, totals.files_with_matches);
+    }
+
+    #[test]
+    fn counts_files_with_matches() {
+        let mut stats = ScanStats::default();
+        stats.count_file(true);
+        stats.count_file(false);
+        stats.count_file(true);
+
+        assert_eq!(3, stats.files);
+        assert_eq!(2, stats.files_with_matches);
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Collects every regular file below `root`, sorted by path.
+///
+/// Symlinks are skipped unless `follow_symlinks` is set, for `--follow`.
+/// Then every file and directory is visited once only, however many links
+/// lead to it, so a link pointing back up the tree can't send the walk into
+/// an endless loop. Broken links are skipped. If `root` is a file, it is
+/// returned as is.
+pub fn collect_files(root: &Path, follow_symlinks: bool) -> io::Result<Vec<PathBuf>> {
+    let mut walk = Walk {
+        follow_symlinks,
+        visited: HashSet::new(),
+        files: Vec::new(),
+    };
+    let meta = fs::metadata(root)?;
+    if meta.is_dir() {
+        walk.visited.insert(file_id(root, &meta)?);
+        walk.visit(root)?;
+        walk.files.sort();
+    } else {
+        walk.files.push(root.to_path_buf());
+    }
+    Ok(walk.files)
+}
+
+struct Walk {
+    follow_symlinks: bool,
+    // what was already reached, only kept when following symlinks
+    visited: HashSet<FileId>,
+    files: Vec<PathBuf>,
+}
+
+impl Walk {
+    fn visit(&mut self, dir: &Path) -> io::Result<()> {
+        // in order, so the same path wins every time a file is reached twice
+        let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|entry| entry.path());
+
+        for entry in entries {
+            let path = entry.path();
+            let mut file_type = entry.file_type()?;
+            if self.follow_symlinks {
+                let meta = match fs::metadata(&path) {
+                    Ok(meta) => meta,
+                    Err(_) if file_type.is_symlink() => continue,
+                    Err(e) => return Err(e),
+                };
+                if !self.visited.insert(file_id(&path, &meta)?) {
+                    continue;
+                }
+                file_type = meta.file_type();
+            }
+            if file_type.is_dir() {
+                self.visit(&path)?;
+            } else if file_type.is_file() {
+                self.files.push(path);
+            }
+        }
+        Ok(())
+    }
+}
+
+// The device and inode number, which are the same for every path to a file.
+#[cfg(unix)]
+type FileId = (u64, u64);
+
+#[cfg(unix)]
+fn file_id(_path: &Path, meta: &fs::Metadata) -> io::Result<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    Ok((meta.dev(), meta.ino()))
+}
+
+// Without inode numbers, the path with every link resolved.
+#[cfg(not(unix))]
+type FileId = PathBuf;
+
+#[cfg(not(unix))]
+fn file_id(path: &Path, _meta: &fs::Metadata) -> io::Result<FileId> {
+    fs::canonicalize(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_nested_files_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("b/c")).unwrap();
+        fs::write(dir.path().join("b/c/deep.txt"), "").unwrap();
+        fs::write(dir.path().join("b/mid.txt"), "").unwrap();
+        fs::write(dir.path().join("a.txt"), "").unwrap();
+
+        let files = collect_files(dir.path(), false).unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|f| f.strip_prefix(dir.path()).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            vec![
+                PathBuf::from("a.txt"),
+                PathBuf::from("b/c/deep.txt"),
+                PathBuf::from("b/mid.txt"),
+            ],
+            names
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn followed_symlinks_are_visited_once() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::create_dir_all(root.join("b")).unwrap();
+        fs::create_dir_all(dir.path().join("outside")).unwrap();
+        fs::write(root.join("a/poem.txt"), "").unwrap();
+        fs::write(dir.path().join("outside/frog.txt"), "").unwrap();
+        // into a directory that is scanned anyway, back up to the root, out
+        // of the tree, and nowhere
+        symlink("../a", root.join("b/again")).unwrap();
+        symlink("..", root.join("b/up")).unwrap();
+        symlink("../outside", root.join("c")).unwrap();
+        symlink("missing", root.join("d")).unwrap();
+
+        let names = |follow_symlinks| -> Vec<PathBuf> {
+            collect_files(&root, follow_symlinks)
+                .unwrap()
+                .iter()
+                .map(|f| f.strip_prefix(&root).unwrap().to_path_buf())
+                .collect()
+        };
+        assert_eq!(vec![PathBuf::from("a/poem.txt")], names(false));
+        assert_eq!(
+            vec![PathBuf::from("a/poem.txt"), PathBuf::from("c/frog.txt")],
+            names(true)
+        );
+    }
+}
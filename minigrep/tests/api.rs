@@ -0,0 +1,243 @@
+// Uses minigrep the way another crate would, through its public API only.
+
+use minigrep::source::{FileSource, Source, StringSource};
+use minigrep::stats::Summary;
+use minigrep::{Config, ConfigError};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+fn config(args: &[&str]) -> Result<Config, ConfigError> {
+    let args = std::iter::once("minigrep").chain(args.iter().copied());
+    Config::new(args.map(String::from))
+}
+
+#[test]
+fn search_functions_borrow_from_the_contents() {
+    let contents = String::from("Rust:\nsafe, fast, productive.\nTrust me.");
+
+    let found: Vec<&str> = minigrep::search("rust", &contents);
+    assert_eq!(vec!["Trust me."], found);
+
+    let found: Vec<&str> = minigrep::search_case_insensitive("rust", &contents);
+    assert_eq!(vec!["Rust:", "Trust me."], found);
+}
+
+#[test]
+fn config_reports_what_was_wrong() {
+    assert_eq!(
+        Some(ConfigError::EmptyQuery),
+        config(&["", "poem.txt"]).err()
+    );
+
+    match config(&["nobody"]) {
+        Err(ConfigError::Usage(message)) => assert!(!message.is_empty()),
+        _ => panic!("a missing file name should be a usage error"),
+    }
+}
+
+#[test]
+fn query_can_come_from_a_reader() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("prices.txt");
+    fs::write(&path, "apples $3 (each)\npears $4\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let mut found = config(&["--query-stdin", path]).unwrap();
+    assert_eq!(vec![path.to_string()], found.filenames);
+    found.read_query(&b"$3 (each)\r\nignored\n"[..]).unwrap();
+    assert_eq!("$3 (each)", found.query);
+
+    let mut out = Vec::new();
+    minigrep::run(found, &mut out).unwrap();
+    assert_eq!("apples $3 (each)\n", String::from_utf8(out).unwrap());
+
+    let mut empty = config(&["--query-stdin", path]).unwrap();
+    assert!(empty.read_query(&b"\n"[..]).is_err());
+}
+
+#[test]
+fn validate_finds_missing_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("poem.txt");
+    fs::write(&path, "I'm nobody! Who are you?\n").unwrap();
+    let missing = dir.path().join("missing.txt");
+    let (path, missing) = (path.to_str().unwrap(), missing.to_str().unwrap());
+
+    assert_eq!(Ok(()), config(&["nobody", path]).unwrap().validate());
+    assert_eq!(
+        Err(ConfigError::FileNotFound(missing.to_string())),
+        config(&["nobody", path, missing]).unwrap().validate()
+    );
+
+    let root = dir.path().to_str().unwrap();
+    assert_eq!(Ok(()), config(&["-r", "nobody", root]).unwrap().validate());
+    let missing_root = format!("{}/missing", root);
+    assert_eq!(
+        Err(ConfigError::FileNotFound(missing_root.clone())),
+        config(&["-r", "nobody", &missing_root]).unwrap().validate()
+    );
+}
+
+#[test]
+fn run_searches_the_configured_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("poem.txt");
+    fs::write(&path, "I'm nobody! Who are you?\n").unwrap();
+
+    let found = config(&["nobody", path.to_str().unwrap()]).unwrap();
+    assert_eq!("nobody", found.query);
+    assert!(minigrep::run(found, &mut io::sink()).is_ok());
+
+    let missing = dir.path().join("missing.txt");
+    let missing = config(&["nobody", missing.to_str().unwrap()]).unwrap();
+    assert!(minigrep::run(missing, &mut io::sink()).is_err());
+}
+
+#[test]
+fn run_writes_the_results_to_the_given_writer() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.txt");
+    let b = dir.path().join("b.txt");
+    fs::write(
+        &a,
+        "I'm nobody! Who are you?
+Are you nobody, too?
+",
+    )
+    .unwrap();
+    fs::write(
+        &b,
+        "How dreary to be somebody!
+nobody at all
+",
+    )
+    .unwrap();
+    let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+
+    let mut out = Vec::new();
+    minigrep::run(config(&["nobody", a, b]).unwrap(), &mut out).unwrap();
+    assert_eq!(
+        format!("{a}:I'm nobody! Who are you?\n{a}:Are you nobody, too?\n{b}:nobody at all\n"),
+        String::from_utf8(out).unwrap()
+    );
+
+    let mut out = Vec::new();
+    minigrep::run(config(&["-c", "nobody", a, b]).unwrap(), &mut out).unwrap();
+    assert_eq!(format!("{a}:2\n{b}:1\n"), String::from_utf8(out).unwrap());
+
+    let mut out = Vec::new();
+    let mut colored = config(&["--count-per-line", "nobody", b]).unwrap();
+    colored.color = true;
+    minigrep::run(colored, &mut out).unwrap();
+    assert_eq!(b"2:1:\x1b[31mnobody\x1b[0m at all\n", out.as_slice());
+}
+
+#[test]
+fn run_returns_the_totals() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.txt");
+    let b = dir.path().join("b.txt");
+    let c = dir.path().join("c.txt");
+    fs::write(&a, "I'm nobody! Who are you?\nAre you nobody, too?\n").unwrap();
+    fs::write(&b, "How dreary to be somebody!\n").unwrap();
+    fs::write(&c, "nobody at all\n").unwrap();
+    let files = [&a, &b, &c].map(|path| path.to_str().unwrap());
+
+    let config = config(&["nobody", files[0], files[1], files[2]]).unwrap();
+    let summary = minigrep::run(config, &mut io::sink()).unwrap();
+    assert_eq!(
+        Summary {
+            files_matched: 2,
+            total_matches: 3
+        },
+        summary
+    );
+}
+
+#[test]
+fn search_files_groups_the_matches_per_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.txt");
+    let b = dir.path().join("b.txt");
+    let missing = dir.path().join("missing.txt");
+    fs::write(&a, "I'm Nobody! Who are you?\nAre you nobody, too?\n").unwrap();
+    fs::write(&b, "How dreary to be somebody!\n").unwrap();
+    let files = vec![a.clone(), b.clone(), missing.clone()];
+
+    let found = minigrep::search_files("nobody", &files, false).unwrap();
+    assert_eq!(2, found.len());
+    assert_eq!(vec![(2, String::from("Are you nobody, too?"))], found[&a]);
+    assert!(found[&b].is_empty());
+    assert!(!found.contains_key(&missing));
+
+    let found = minigrep::search_files("nobody", &files, true).unwrap();
+    assert_eq!(
+        vec![
+            (1, String::from("I'm Nobody! Who are you?")),
+            (2, String::from("Are you nobody, too?")),
+        ],
+        found[&a]
+    );
+    assert!(found[&b].is_empty());
+}
+
+#[test]
+fn search_files_fails_on_binary_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("image.bin");
+    fs::write(&path, [0xFF, 0xFE, b'\n']).unwrap();
+
+    assert!(minigrep::search_files("nobody", &[PathBuf::from(&path)], false).is_err());
+}
+
+#[test]
+fn run_sources_searches_text_in_memory() {
+    let sources: Vec<Box<dyn Source>> = vec![
+        Box::new(StringSource::new(
+            "emily",
+            "I'm nobody! Who are you?\nAre you nobody, too?\n",
+        )),
+        Box::new(StringSource::new("empty", "")),
+        Box::new(StringSource::new(
+            "frog",
+            "How public, like a frog\nnobody\n",
+        )),
+    ];
+    let config = config(&["nobody", "unused.txt"]).unwrap();
+
+    let mut out = Vec::new();
+    let summary = minigrep::run_sources(&config, &sources, &mut out).unwrap();
+    assert_eq!(
+        "emily:I'm nobody! Who are you?\nemily:Are you nobody, too?\nfrog:nobody\n",
+        String::from_utf8(out).unwrap()
+    );
+    assert_eq!(
+        Summary {
+            files_matched: 2,
+            total_matches: 3
+        },
+        summary
+    );
+}
+
+#[test]
+fn run_sources_reports_sources_that_cant_be_read() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("poem.txt");
+    fs::write(&path, "I'm nobody! Who are you?\n").unwrap();
+    let sources: Vec<Box<dyn Source>> = vec![
+        Box::new(FileSource::new(&path)),
+        Box::new(FileSource::new(dir.path().join("missing.txt"))),
+    ];
+    assert_eq!(path.to_str().unwrap(), sources[0].name());
+
+    let config = config(&["nobody", "unused.txt"]).unwrap();
+    let mut out = Vec::new();
+    let err = minigrep::run_sources(&config, &sources, &mut out).unwrap_err();
+    assert!(err.is::<minigrep::FileErrors>());
+    assert_eq!(
+        format!("{}:I'm nobody! Who are you?\n", path.display()),
+        String::from_utf8(out).unwrap()
+    );
+}
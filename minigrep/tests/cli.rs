@@ -0,0 +1,1034 @@
+use std::fs;
+use std::process::Command;
+
+fn minigrep(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_minigrep"))
+        .args(args)
+        .env_remove("CASE_INSENSITIVE")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn recursive_progress_never_reaches_stdout() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    fs::write(dir.path().join("a.txt"), "I'm nobody! Who are you?\n").unwrap();
+    fs::write(
+        dir.path().join("sub/b.txt"),
+        "Are you nobody, too?\nHow dreary\n",
+    )
+    .unwrap();
+
+    let root = dir.path().to_str().unwrap();
+    let output = minigrep(&["-r", "--progress", "nobody", root]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        format!("{root}/a.txt:I'm nobody! Who are you?\n{root}/sub/b.txt:Are you nobody, too?\n"),
+        stdout
+    );
+    assert!(!stdout.contains("scanned"));
+}
+
+#[test]
+fn max_count_keeps_the_trailing_context() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("poem.txt");
+    fs::write(
+        &path,
+        "I'm nobody! Who are you?\nAre you nobody, too?\nThen there's a pair of us\nnobody again\n",
+    )
+    .unwrap();
+
+    let output = minigrep(&["-m", "1", "-A", "2", "nobody", path.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        "I'm nobody! Who are you?\nAre you nobody, too?\nThen there's a pair of us\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn tail_follow_prints_new_matches() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.log");
+    fs::write(&path, "nobody was here before\n").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_minigrep"))
+        .args(["-f", "nobody", path.to_str().unwrap()])
+        .env_remove("CASE_INSENSITIVE")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stdout = child.stdout.take().unwrap();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            sender.send(line.unwrap()).unwrap();
+        }
+    });
+
+    // give minigrep time to open the file and seek to its end
+    thread::sleep(Duration::from_millis(500));
+    let mut log = fs::OpenOptions::new().append(true).open(&path).unwrap();
+    log.write_all(b"How dreary\nAre you nobody, too?\n")
+        .unwrap();
+
+    let line = receiver.recv_timeout(Duration::from_secs(5));
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert_eq!(Ok(String::from("Are you nobody, too?")), line);
+}
+
+#[test]
+fn name_search_matches_paths_not_contents() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("nobody.txt"), "Who are you?\n").unwrap();
+    fs::write(dir.path().join("poem.txt"), "I'm nobody!\n").unwrap();
+    let root = dir.path().to_str().unwrap();
+
+    let output = minigrep(&["-r", "--name", "nobody", root]);
+    assert_eq!(
+        format!("{root}/nobody.txt\n"),
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    let output = minigrep(&["-r", "nobody", root]);
+    assert_eq!(
+        format!("{root}/poem.txt:I'm nobody!\n"),
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    let output = minigrep(&["-r", "--name", "--content", "nobody", root]);
+    assert_eq!(
+        format!("name: {root}/nobody.txt\ncontent: {root}/poem.txt:I'm nobody!\n"),
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn with_count_prints_the_lines_and_the_total_to_stderr() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("poem.txt");
+    fs::write(
+        &path,
+        "I'm nobody! Who are you?\nAre you nobody, too?\nHow dreary\n",
+    )
+    .unwrap();
+
+    let output = minigrep(&["--with-count", "nobody", path.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        "I'm nobody! Who are you?\nAre you nobody, too?\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+    assert_eq!(
+        "Total: 2 matches\n",
+        String::from_utf8(output.stderr).unwrap()
+    );
+}
+
+#[test]
+fn sort_matches_prints_the_lines_of_a_file_in_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("fruit.txt");
+    fs::write(&path, "pear: 4\nfig\napple: 3\ncherry: 12\nbanana: 5\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = minigrep(&["--sort-matches", ":", path]);
+    assert!(output.status.success());
+    assert_eq!(
+        "apple: 3\nbanana: 5\ncherry: 12\npear: 4\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    let output = minigrep(&["--sort-matches=reverse", ":", path]);
+    assert_eq!(
+        "pear: 4\ncherry: 12\nbanana: 5\napple: 3\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    let output = minigrep(&["--sort-matches", "-A", "1", ":", path]);
+    assert_eq!(Some(1), output.status.code());
+}
+
+#[test]
+fn max_filesize_skips_bigger_files() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("small.txt"), "nobody\n").unwrap();
+    let mut dump = "x".repeat(2048);
+    dump.push_str("\nnobody\n");
+    fs::write(dir.path().join("dump.txt"), dump).unwrap();
+    let root = dir.path().to_str().unwrap();
+
+    let output = minigrep(&["-r", "--max-filesize", "1K", "nobody", root]);
+    assert!(output.status.success());
+    assert_eq!(
+        format!("{root}/small.txt:nobody\n"),
+        String::from_utf8(output.stdout).unwrap()
+    );
+    assert!(output.stderr.is_empty());
+
+    let output = minigrep(&["-r", "--max-filesize", "1K", "--verbose", "nobody", root]);
+    assert_eq!(
+        format!("minigrep: {root}/dump.txt: skipped, bigger than --max-filesize\n"),
+        String::from_utf8(output.stderr).unwrap()
+    );
+
+    let output = minigrep(&["-r", "--max-filesize", "1MB", "nobody", root]);
+    assert_eq!(Some(1), output.status.code());
+}
+
+#[test]
+fn files_after_double_dash_can_start_with_a_dash() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("-weird.txt"), "I'm nobody!\nHow dreary\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_minigrep"))
+        .args(["nobody", "--", "-weird.txt"])
+        .current_dir(dir.path())
+        .env_remove("CASE_INSENSITIVE")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!("I'm nobody!\n", String::from_utf8(output.stdout).unwrap());
+}
+
+#[test]
+fn histogram_charts_the_matches_of_each_file() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(
+        dir.path().join("src/a.txt"),
+        "nobody\nnobody\nnobody\nnobody\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("b.txt"), "nobody, too\n").unwrap();
+    fs::write(dir.path().join("c.txt"), "somebody\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_minigrep"))
+        .args(["-r", "--histogram", "nobody", "."])
+        .current_dir(dir.path())
+        .env("COLUMNS", "20")
+        .env_remove("CASE_INSENSITIVE")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        "./src/a.txt ██████ 4\n./b.txt     ██ 1\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn align_pads_file_names_of_different_lengths() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("poems")).unwrap();
+    fs::write(dir.path().join("a.txt"), "I'm nobody!\n").unwrap();
+    let lines: Vec<_> = (1..=12).map(|n| format!("line {}", n)).collect();
+    let mut long = lines.join("\n");
+    long.push_str("\nAre you nobody, too?\n");
+    fs::write(dir.path().join("poems/dickinson.txt"), long).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_minigrep"))
+        .args(["-n", "--align", "nobody", "a.txt", "poems/dickinson.txt"])
+        .current_dir(dir.path())
+        .env_remove("CASE_INSENSITIVE")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        "a.txt:               1:I'm nobody!\n\
+         poems/dickinson.txt:13:Are you nobody, too?\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn align_stops_at_max_output_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    // the 10 bytes of the name are a newline when counted as a byte
+    fs::write(dir.path().join("poem10.txt"), "nobody\nnobody\n").unwrap();
+    fs::write(dir.path().join("aa.txt"), "nobody\nnobody\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_minigrep"))
+        .args(["-n", "--align", "--max-output-lines", "3", "nobody"])
+        .args(["poem10.txt", "aa.txt"])
+        .current_dir(dir.path())
+        .env_remove("CASE_INSENSITIVE")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        "poem10.txt:1:nobody\n\
+         poem10.txt:2:nobody\n\
+         aa.txt:    1:nobody\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("(output truncated)"));
+}
+
+#[test]
+fn json_lines_have_the_byte_ranges_of_the_matches() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cafés.txt");
+    fs::write(&path, "un café, deux cafés\nthé\n\"café\"\tau lait\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = minigrep(&["--json", "café", path]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let objects: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(2, objects.len());
+    for (object, number) in objects.iter().zip([1, 3]) {
+        assert_eq!(path, object["path"]);
+        assert_eq!(number, object["line_number"]);
+    }
+    assert_eq!("un café, deux cafés", objects[0]["text"]);
+    assert_eq!(serde_json::json!([[3, 8], [15, 20]]), objects[0]["ranges"]);
+    assert_eq!("\"café\"\tau lait", objects[1]["text"]);
+    assert_eq!(serde_json::json!([[1, 6]]), objects[1]["ranges"]);
+
+    // the ranges cut the text right where the query is
+    for object in &objects {
+        let text = object["text"].as_str().unwrap();
+        for range in object["ranges"].as_array().unwrap() {
+            let start = range[0].as_u64().unwrap() as usize;
+            let end = range[1].as_u64().unwrap() as usize;
+            assert_eq!("café", &text[start..end]);
+        }
+    }
+}
+
+#[test]
+fn line_numbers_tell_matches_from_context() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("poem.txt");
+    fs::write(
+        &path,
+        "I'm nobody! Who are you?\nAre you nobody, too?\nHow dreary to be somebody!\n\
+         How public, like a frog\nTo tell your name the livelong day\nTo an admiring bog!\n\
+         How dreary\n",
+    )
+    .unwrap();
+
+    let output = minigrep(&["-n", "-C", "1", "dreary", path.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        "2-Are you nobody, too?\n\
+         3:How dreary to be somebody!\n\
+         4-How public, like a frog\n\
+         --\n\
+         6-To an admiring bog!\n\
+         7:How dreary\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn count_bytes_adds_up_the_matching_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.log");
+    let b = dir.path().join("b.log");
+    // 9 + 1 and 15 + 1 bytes match
+    fs::write(&a, "ERROR one\nok\nERROR two three\nok again\n").unwrap();
+    fs::write(&b, "ok\n").unwrap();
+    let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+
+    let output = minigrep(&["--count-bytes", "ERROR", a]);
+    assert!(output.status.success());
+    assert_eq!("26\n", String::from_utf8(output.stdout).unwrap());
+
+    // the other lines: 2 + 1 and 8 + 1
+    let output = minigrep(&["--count-bytes", "-v", "ERROR", a, b]);
+    assert_eq!(
+        format!("{a}:12\n{b}:3\n"),
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn summary_counts_matching_files() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    fs::write(dir.path().join("a.txt"), "I'm nobody! Who are you?\n").unwrap();
+    fs::write(
+        dir.path().join("sub/b.txt"),
+        "Are you nobody, too?\nnobody again\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("sub/c.txt"), "How dreary\n").unwrap();
+    fs::write(dir.path().join("image.bin"), [0xff, 0xfe, 0x00]).unwrap();
+
+    let output = minigrep(&["-r", "--summary", "nobody", dir.path().to_str().unwrap()]);
+
+    assert!(output.status.success());
+    assert_eq!(3, String::from_utf8(output.stdout).unwrap().lines().count());
+    assert_eq!(
+        "3 matches in 2 files (scanned 3 files)\n",
+        String::from_utf8(output.stderr).unwrap()
+    );
+}
+
+#[test]
+fn encoding_detect_strips_the_bom() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("poem.txt");
+    fs::write(&path, "\u{feff}nobody\nWho are you?\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = minigrep(&["nobody", path]);
+    assert_eq!(
+        "\u{feff}nobody\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    let output = minigrep(&["--encoding-detect", "nobody", path]);
+    assert_eq!("nobody\n", String::from_utf8(output.stdout).unwrap());
+}
+
+#[test]
+fn regex_replacement_uses_capture_groups() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("contacts.txt");
+    fs::write(&path, "mail jane@example\nno address here\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = minigrep(&["-E", "--replace", "$2.$1", r"(\w+)@(\w+)", path]);
+    assert_eq!(
+        "mail example.jane\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    minigrep(&["-E", "--replace", "$$$1", "--in-place", r"(\w+)@\w+", path]);
+    assert_eq!(
+        "mail $jane\nno address here\n",
+        fs::read_to_string(path).unwrap()
+    );
+}
+
+#[test]
+fn unreadable_files_are_skipped_by_default() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "I'm nobody!\n").unwrap();
+    fs::write(dir.path().join("b.txt"), "Are you nobody, too?\n").unwrap();
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    let path = |name: &str| dir.path().join(name).to_str().unwrap().to_string();
+
+    let output = minigrep(&[
+        "nobody",
+        &path("a.txt"),
+        &path("missing.txt"),
+        &path("sub"),
+        &path("b.txt"),
+    ]);
+
+    assert_eq!(Some(2), output.status.code());
+    assert_eq!(
+        format!(
+            "{}:I'm nobody!\n{}:Are you nobody, too?\n",
+            path("a.txt"),
+            path("b.txt")
+        ),
+        String::from_utf8(output.stdout).unwrap()
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(&format!("minigrep: {}:", path("missing.txt"))));
+    assert!(stderr.contains(&format!(
+        "minigrep: {}: is a directory, use -r to search it",
+        path("sub")
+    )));
+    assert!(stderr.ends_with("Application error: 2 files couldn't be read\n"));
+
+    let output = minigrep(&["nobody", &path("a.txt"), &path("sub")]);
+    assert_eq!(Some(2), output.status.code());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .ends_with("Application error: 1 file couldn't be read\n"));
+}
+
+#[test]
+fn directories_need_recursion() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "I'm nobody!\n").unwrap();
+    let root = dir.path().to_str().unwrap();
+
+    let output = minigrep(&["--stop-on-error", "nobody", root]);
+    assert_eq!(Some(1), output.status.code());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains(&format!("{root}: is a directory, use -r to search it")));
+
+    let output = minigrep(&["-r", "nobody", root]);
+    assert!(output.status.success());
+    assert_eq!(
+        format!("{root}/a.txt:I'm nobody!\n"),
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn missing_file_fails_before_searching() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "I'm nobody!\n").unwrap();
+    let path = |name: &str| dir.path().join(name).to_str().unwrap().to_string();
+
+    let output = minigrep(&["nobody", &path("missing.txt")]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        format!(
+            "Problem parsing arguments: {}: No such file or directory\n",
+            path("missing.txt")
+        ),
+        String::from_utf8(output.stderr).unwrap()
+    );
+
+    let output = minigrep(&[
+        "--stop-on-error",
+        "nobody",
+        &path("a.txt"),
+        &path("missing.txt"),
+    ]);
+    assert_eq!(Some(1), output.status.code());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn stop_on_error_aborts_at_the_first_failure() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "I'm nobody!\n").unwrap();
+    fs::write(dir.path().join("b.txt"), "Are you nobody, too?\n").unwrap();
+    // missing files are found before the search, but a directory only when
+    // it is reached
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    let path = |name: &str| dir.path().join(name).to_str().unwrap().to_string();
+
+    let output = minigrep(&[
+        "--stop-on-error",
+        "nobody",
+        &path("a.txt"),
+        &path("sub"),
+        &path("b.txt"),
+    ]);
+
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        format!("{}:I'm nobody!\n", path("a.txt")),
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    let output = minigrep(&["nobody", &path("a.txt"), &path("b.txt")]);
+    assert_eq!(Some(0), output.status.code());
+}
+
+#[test]
+fn count_prints_matching_lines_per_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.txt");
+    let b = dir.path().join("b.txt");
+    fs::write(&a, "I'm nobody!\nAre you nobody, too?\nHow dreary\n").unwrap();
+    fs::write(&b, "Who are you?\n").unwrap();
+    let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+
+    let output = minigrep(&["-c", "nobody", a]);
+    assert_eq!("2\n", String::from_utf8(output.stdout).unwrap());
+
+    let output = minigrep(&["--count", "nobody", a, b]);
+    assert_eq!(
+        format!("{a}:2\n{b}:0\n"),
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn replace_count_reports_totals() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("poem.txt");
+    fs::write(
+        &path,
+        "nobody, nobody, nobody!\nWho are you?\nAre you nobody, too?\n",
+    )
+    .unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = minigrep(&["--replace", "somebody", "--replace-count", "nobody", path]);
+    assert_eq!(
+        "4 replacements on 2 lines\n",
+        String::from_utf8(output.stderr).unwrap()
+    );
+
+    let output = minigrep(&[
+        "--replace",
+        "somebody",
+        "--in-place",
+        "--replace-count",
+        "nobody",
+        path,
+    ]);
+    assert_eq!(
+        "4 replacements on 2 lines\n",
+        String::from_utf8(output.stderr).unwrap()
+    );
+    assert!(!fs::read_to_string(path).unwrap().contains("nobody"));
+}
+
+#[test]
+fn first_prints_only_the_first_match_with_its_position() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.rs");
+    let b = dir.path().join("b.rs");
+    fs::write(
+        &a,
+        "use std::fs;\n\nfn main() {\n    let x = 1;\n}\n\n  fn main_again() {}\n",
+    )
+    .unwrap();
+    fs::write(&b, "// nothing here\n").unwrap();
+    let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+
+    let output = minigrep(&["--first", "fn main", a]);
+    assert_eq!(
+        "3:1:fn main() {\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    let output = minigrep(&["--first", "main_again", a, b]);
+    assert_eq!(
+        format!("{a}:7:6:  fn main_again() {{}}\n"),
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn redirected_output_is_never_colored() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("poem.txt");
+    fs::write(&path, "I'm nobody! Who are you?\nAre you nobody, too?\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = minigrep(&["nobody", path]);
+    assert_eq!(
+        "I'm nobody! Who are you?\nAre you nobody, too?\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    let output = minigrep(&["--replace", "somebody", "--dry-run", "nobody", path]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("- I'm nobody!"));
+    assert!(!stdout.contains('\x1b'));
+}
+
+#[test]
+fn mmap_finds_the_same_lines_in_a_large_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("big.log");
+    let mut contents = String::new();
+    for i in 0..200_000 {
+        if i % 997 == 0 {
+            contents.push_str(&format!("{i}: I'm nobody! Who are you?\n"));
+        } else {
+            contents.push_str(&format!("{i}: How dreary to be somebody!\n"));
+        }
+    }
+    fs::write(&path, &contents).unwrap();
+    let path = path.to_str().unwrap();
+
+    let read = minigrep(&["-C", "1", "nobody", path]);
+    let mapped = minigrep(&["--mmap", "-C", "1", "nobody", path]);
+
+    assert!(mapped.status.success());
+    // the first match has no line before it
+    let lines = read.stdout.iter().filter(|&&b| b == b'\n').count();
+    assert_eq!(201 * 3 - 1 + 200, lines);
+    assert_eq!(read.stdout, mapped.stdout);
+}
+
+#[test]
+fn files_without_match_lists_only_files_missing_the_query() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(
+        dir.path().join("main.rs"),
+        "// License: MIT\nfn main() {}\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("src/lib.rs"), "pub fn run() {}\n").unwrap();
+    fs::write(
+        dir.path().join("src/util.rs"),
+        "fn helper() {}\n// License: MIT\n",
+    )
+    .unwrap();
+
+    let root = dir.path().to_str().unwrap();
+    let output = minigrep(&["-r", "-L", "License:", root]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        format!("{root}/src/lib.rs\n"),
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn byte_offset_counts_every_byte_before_the_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("poem.txt");
+    // "é" takes two bytes, "😻" four, and the "\r\n" both count
+    fs::write(&path, "café nobody\r\n😻 somebody\nnobody else\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = minigrep(&["-b", "nobody", path]);
+    assert!(output.status.success());
+    assert_eq!(
+        "0:café nobody\n28:nobody else\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    let output = minigrep(&["--byte-offset", "-A", "1", "café", path]);
+    assert_eq!(
+        "0:café nobody\n14-😻 somebody\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn field_separator_replaces_the_colons() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("12:00.log");
+    let b = dir.path().join("b.txt");
+    fs::write(&a, "I'm nobody! Who are you?\nHow dreary\n").unwrap();
+    fs::write(&b, "Are you nobody, too?\n").unwrap();
+    let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+
+    let output = minigrep(&["--field-separator", "|", "-A", "1", "nobody", a, b]);
+    assert!(output.status.success());
+    assert_eq!(
+        format!("{a}|I'm nobody! Who are you?\n{a}-How dreary\n\n{b}|Are you nobody, too?\n"),
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    let output = minigrep(&["--field-separator", "\\0", "--first", "you", a, b]);
+    assert_eq!(
+        format!("{a}\01\021\0I'm nobody! Who are you?\n{b}\01\05\0Are you nobody, too?\n"),
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn null_data_splits_and_ends_lines_at_nul() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("files.txt");
+    // like `find -print0`, where a name can even contain a newline
+    fs::write(&path, "./poem.txt\0./notes\nabout poems.md\0./todo.txt\0").unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = minigrep(&["--null-data", "poem", path]);
+    assert!(output.status.success());
+    assert_eq!(
+        "./poem.txt\0./notes\nabout poems.md\0",
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    let output = minigrep(&["-z", "-b", "todo", path]);
+    assert_eq!("34:./todo.txt\0", String::from_utf8(output.stdout).unwrap());
+}
+
+#[test]
+fn max_output_lines_truncates_across_files() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "nobody 1\nnobody 2\n").unwrap();
+    fs::write(dir.path().join("b.txt"), "nobody 3\nnobody 4\n").unwrap();
+    fs::write(dir.path().join("c.txt"), "nobody 5\n").unwrap();
+    let root = dir.path().to_str().unwrap();
+
+    let output = minigrep(&["-r", "--max-output-lines", "3", "nobody", root]);
+    assert!(output.status.success());
+    assert_eq!(
+        format!("{root}/a.txt:nobody 1\n{root}/a.txt:nobody 2\n{root}/b.txt:nobody 3\n"),
+        String::from_utf8(output.stdout).unwrap()
+    );
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("... (output truncated)"));
+
+    let output = minigrep(&["-r", "-j", "2", "--max-output-lines", "3", "nobody", root]);
+    assert_eq!(3, String::from_utf8(output.stdout).unwrap().lines().count());
+
+    // exactly as many lines as allowed isn't truncated
+    let output = minigrep(&["-r", "--max-output-lines", "5", "nobody", root]);
+    assert!(!String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("truncated"));
+}
+
+#[test]
+fn inverted_count_is_every_other_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("poem.txt");
+    let lines = [
+        "I'm nobody! Who are you?",
+        "Are you nobody, too?",
+        "Then there's a pair of us",
+        "Don't tell!",
+        "They'd advertise, you know.",
+        "How dreary to be somebody!",
+        "How public, like a frog",
+        "To tell one's name the livelong day",
+        "To an admiring bog!",
+        "nobody at all",
+    ];
+    fs::write(&path, lines.join("\n")).unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = minigrep(&["-c", "nobody", path]);
+    assert_eq!("3\n", String::from_utf8(output.stdout).unwrap());
+    let output = minigrep(&["-vc", "nobody", path]);
+    assert_eq!("7\n", String::from_utf8(output.stdout).unwrap());
+    let output = minigrep(&["-v", "-c", "-m", "5", "nobody", path]);
+    assert_eq!("5\n", String::from_utf8(output.stdout).unwrap());
+
+    let output = minigrep(&["-v", "e", path]);
+    assert_eq!(
+        "To an admiring bog!\nnobody at all\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn word_regexp_skips_parts_of_words() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("poem.txt");
+    fs::write(&path, "I'm nobody! Who are you?\nbody and soul\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = minigrep(&["-w", "body", path]);
+    assert_eq!("body and soul\n", String::from_utf8(output.stdout).unwrap());
+    let output = minigrep(&["-w", "--first", "soul", path]);
+    assert_eq!(
+        "2:10:body and soul\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn count_per_line_shows_the_occurrences_on_each_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("poem.txt");
+    fs::write(
+        &path,
+        "Who are you?\nnobody, nobody, nobody!\nAre you nobody, too?\n",
+    )
+    .unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = minigrep(&["--count-per-line", "nobody", path]);
+    assert_eq!(
+        "2:3:nobody, nobody, nobody!\n3:1:Are you nobody, too?\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+    let output = minigrep(&["--count-per-line", "--regex", "-w", "[a-z]+dy", path, path]);
+    assert_eq!(
+        format!(
+            "{0}:2:3:nobody, nobody, nobody!\n{0}:3:1:Are you nobody, too?\n",
+            path
+        )
+        .repeat(2),
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn follow_searches_linked_directories_once() {
+    use std::os::unix::fs::symlink;
+
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("poems")).unwrap();
+    fs::write(dir.path().join("poems/nobody.txt"), "I'm nobody!\n").unwrap();
+    symlink("poems", dir.path().join("favorites")).unwrap();
+    symlink(".", dir.path().join("poems/loop")).unwrap();
+    let root = dir.path().to_str().unwrap();
+
+    let output = minigrep(&["-r", "-c", "--follow", "nobody", root]);
+    assert!(output.status.success());
+    assert_eq!(
+        format!("{}/favorites/nobody.txt:1\n", root),
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    let output = minigrep(&["--follow", "nobody", root]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn max_columns_truncates_long_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("min.js");
+    let long = format!("var nobody = 1;{}nobody()", "x".repeat(1000));
+    fs::write(&path, format!("{}\nshort nobody\n", long)).unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = minigrep(&["--max-columns", "12", "nobody", path]);
+    assert_eq!(
+        "var nobody =…\nshort nobody\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+    // the lines are still counted in full
+    let output = minigrep(&["--max-columns", "12", "--count-per-line", "nobody", path]);
+    assert_eq!(
+        "1:2:var nobody =…\n2:1:short nobody\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn trim_strips_the_indentation_of_matching_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("main.rs");
+    fs::write(&path, "fn main() {\n        let nobody = 1;  \n}\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = minigrep(&["--trim", "nobody", path]);
+    assert_eq!(
+        "let nobody = 1;\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+    // columns and offsets are still those of the whole line
+    let output = minigrep(&["--trim", "--first", "nobody", path]);
+    assert_eq!(
+        "2:13:let nobody = 1;\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+    let output = minigrep(&["--trim", "-b", "nobody", path]);
+    assert_eq!(
+        "12:let nobody = 1;\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn output_dir_mirrors_the_replaced_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let src = dir.path().join("src");
+    fs::create_dir_all(src.join("poems/short")).unwrap();
+    fs::write(src.join("a.txt"), "I'm nobody!\n").unwrap();
+    fs::write(src.join("poems/short/b.txt"), "Are you nobody, too?\n").unwrap();
+    fs::write(src.join("poems/c.txt"), "How dreary\n").unwrap();
+    let out = dir.path().join("out");
+
+    let output = minigrep(&[
+        "-r",
+        "--replace",
+        "somebody",
+        "--output-dir",
+        out.to_str().unwrap(),
+        "nobody",
+        src.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    assert_eq!(
+        "I'm somebody!\n",
+        fs::read_to_string(out.join("a.txt")).unwrap()
+    );
+    assert_eq!(
+        "Are you somebody, too?\n",
+        fs::read_to_string(out.join("poems/short/b.txt")).unwrap()
+    );
+    // nothing was replaced in c.txt, so there is no copy of it
+    assert!(!out.join("poems/c.txt").exists());
+
+    assert_eq!(
+        "I'm nobody!\n",
+        fs::read_to_string(src.join("a.txt")).unwrap()
+    );
+    assert_eq!(
+        "Are you nobody, too?\n",
+        fs::read_to_string(src.join("poems/short/b.txt")).unwrap()
+    );
+}
+
+#[test]
+fn include_and_exclude_pick_the_searched_files() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("src/bin")).unwrap();
+    fs::write(dir.path().join("build.rs"), "// nobody\n").unwrap();
+    fs::write(dir.path().join("notes.txt"), "nobody\n").unwrap();
+    fs::write(dir.path().join("src/lib.rs"), "// nobody\n").unwrap();
+    fs::write(dir.path().join("src/bin/tool.rs"), "// nobody\n").unwrap();
+    let root = dir.path().to_str().unwrap();
+
+    let files = |args: &[&str]| {
+        let mut args = args.to_vec();
+        args.extend(["-r", "-c", "nobody", root]);
+        let stdout = String::from_utf8(minigrep(&args).stdout).unwrap();
+        stdout
+            .lines()
+            .map(|line| line.strip_prefix(root).unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec!["/build.rs:1"], files(&["--include", "*.rs"]));
+    assert_eq!(
+        vec!["/build.rs:1", "/src/bin/tool.rs:1", "/src/lib.rs:1"],
+        files(&["--include", "**/*.rs"])
+    );
+    assert_eq!(
+        vec!["/build.rs:1", "/src/lib.rs:1"],
+        files(&["--include", "**/*.rs", "--exclude", "src/bin/**"])
+    );
+}
+
+#[test]
+fn group_separator_only_goes_between_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.txt");
+    let b = dir.path().join("b.txt");
+    let c = dir.path().join("c.txt");
+    let d = dir.path().join("d.txt");
+    fs::write(&a, "I'm nobody! Who are you?\nAre you nobody, too?\n").unwrap();
+    fs::write(&b, "How dreary to be somebody!\n").unwrap();
+    fs::write(&c, "nobody at all\n").unwrap();
+    fs::write(&d, "How public, like a frog\n").unwrap();
+    let files = [&a, &b, &c, &d].map(|path| path.to_str().unwrap());
+    let (a, c) = (files[0], files[2]);
+
+    let mut args = vec!["--heading", "nobody"];
+    args.extend(files);
+    let output = minigrep(&args);
+    assert_eq!(
+        format!("{a}\nI'm nobody! Who are you?\nAre you nobody, too?\n\n{c}\nnobody at all\n"),
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    let mut args = vec!["--heading", "--group-separator", "==", "-j", "2", "nobody"];
+    args.extend(files);
+    let stdout = String::from_utf8(minigrep(&args).stdout).unwrap();
+    // the threads may finish the files in any order
+    let a_first =
+        format!("{a}\nI'm nobody! Who are you?\nAre you nobody, too?\n==\n{c}\nnobody at all\n");
+    let c_first =
+        format!("{c}\nnobody at all\n==\n{a}\nI'm nobody! Who are you?\nAre you nobody, too?\n");
+    assert!(stdout == a_first || stdout == c_first, "{}", stdout);
+}
@@ -1,59 +1,79 @@
-use server::ThreadPool;
-use std::thread;
-use std::time::Duration;
-use std::fs;
-// to read and write from the stream
-use std::io::prelude::*;
+use server::{ConnectionLimiter, FileCache, RateLimiter, Response, ServerConfig, ThreadPool};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::io;
 use std::net::TcpStream;
-use std::net::TcpListener;
+use std::process;
+
+const ADDRESS: &str = "127.0.0.1:7878";
 
 fn main() {
-    // listen to tcp connections at specified address
-    // 7878 is the port
-    // bind will return a new tcplistener instance
-    // bind means binding to a port
-    // bind returns Result<T, E> i.e. the binding might fail
-    let listener = TcpListener::bind("127.0.0.1:7878").unwrap();
+    // a backlog of 128 and TCP_NODELAY on every connection
+    let config = ServerConfig::default();
+    // listen for tcp connections on port 7878, with that backlog; a restart
+    // may find the port still taken for a moment, so binding is tried up to
+    // 5 times, waiting a little longer each time
+    let listener = server::bind_with_config(ADDRESS, 5, &config).unwrap_or_else(|e| {
+        eprintln!("Couldn't listen on {}: {}", ADDRESS, e);
+        process::exit(1);
+    });
     let pool = ThreadPool::new(4);
-    // incoming is an iterator that returns a sequence of streams
+    // at most 10 requests per second from the same IP
+    let limiter = Arc::new(RateLimiter::new(10));
+    // at most 8 connections at once, whether handled or queued for the pool
+    let connections = Arc::new(ConnectionLimiter::new(8));
+    // the 32 most recently served files, shared by the workers
+    let cache = FileCache::new(config.cache_size);
+    // the accept loop checks this flag between connections
+    let shutdown = AtomicBool::new(false);
+    let mut handled = 0;
     // each stream resembles an open connection between client and server
-    for stream in listener.incoming().take(2) {
-        // unwrap will terminate our program if the stream has any errors
-        let stream = stream.unwrap();
-
-        pool.execute(|| {
-            handle_connection(stream);
+    server::accept_loop(&listener, &config, &shutdown, |stream| {
+        let Some(guard) = connections.try_acquire() else {
+            if let Err(e) = reject_unavailable(stream) {
+                eprintln!("Failed to answer a connection: {}", e);
+            }
+            return;
+        };
+        let limiter = Arc::clone(&limiter);
+        let cache = cache.clone();
+        pool.execute(move || {
+            // the slot is freed when the connection is done
+            let _guard = guard;
+            // without a peer address the connection is already gone
+            let Ok(peer) = stream.peer_addr() else {
+                return;
+            };
+            let result = if limiter.check(peer.ip()) {
+                server::http::handle_connection(stream, peer, &cache)
+            } else {
+                reject_too_many_requests(stream)
+            };
+            // the client may be gone already, that's no reason to stop
+            if let Err(e) = result {
+                eprintln!("Failed to answer a connection: {}", e);
+            }
         });
-    }
+
+        // shut down after two requests
+        handled += 1;
+        if handled == 2 {
+            shutdown.store(true, Ordering::SeqCst);
+        }
+    })
+    .unwrap_or_else(|e| {
+        // only an error from the listener itself gets here
+        eprintln!("Stopped listening on {}: {}", ADDRESS, e);
+        process::exit(1);
+    });
 
     println!("Shutting down");
 }
 
-fn handle_connection(mut stream: TcpStream) {
-    let mut buffer = [0; 1024];
-    stream.read(&mut buffer).unwrap();
-
-    let get = b"GET / HTTP/1.1\r\n";
-    let sleep = b"GET /sleep HTTP/1.1\r\n";
-
-    let (status_line, filename) = if buffer.starts_with(get) {
-        ("HTTP/1.1 200 OK", "hello.html")
-    } else if buffer.starts_with(sleep) {
-        thread::sleep(Duration::from_secs(5));
-        ("HTTP/1.1 200 OK", "hello.html")
-    } else {
-        ("HTTP/1.1 404 NOT FOUND", "404.html")
-    };
-    let contents = fs::read_to_string(filename).unwrap();
-
-    let response = format!(
-        "{}\r\nContent-Length: {}\r\n\r\n{}",
-        status_line,
-        contents.len(),
-        contents
-    );
-
-    stream.write(response.as_bytes()).unwrap();
-    stream.flush().unwrap();    
+fn reject_unavailable(mut stream: TcpStream) -> io::Result<()> {
+    Response::new(503).write_to(&mut stream)
 }
 
+fn reject_too_many_requests(mut stream: TcpStream) -> io::Result<()> {
+    Response::new(429).write_to(&mut stream)
+}
@@ -0,0 +1,112 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A counting semaphore bounding how many connections are handled at once.
+///
+/// Each connection holds a [`ConnectionGuard`] while it is handled; when
+/// `max` guards are out, new connections should be rejected with
+/// `503 Service Unavailable`.
+pub struct ConnectionLimiter {
+    max: usize,
+    active: AtomicUsize,
+}
+
+/// Keeps one connection slot taken until it is dropped.
+pub struct ConnectionGuard {
+    limiter: Arc<ConnectionLimiter>,
+}
+
+impl ConnectionLimiter {
+    /// Create a new ConnectionLimiter allowing `max` connections at once.
+    ///
+    /// # Panics
+    ///
+    /// The `new` function will panic if `max` is zero.
+    pub fn new(max: usize) -> ConnectionLimiter {
+        assert!(max > 0);
+
+        ConnectionLimiter {
+            max,
+            active: AtomicUsize::new(0),
+        }
+    }
+
+    /// Take a slot, or return `None` if all of them are in use.
+    pub fn try_acquire(self: &Arc<Self>) -> Option<ConnectionGuard> {
+        self.active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |active| {
+                (active < self.max).then_some(active + 1)
+            })
+            .ok()?;
+
+        Some(ConnectionGuard {
+            limiter: Arc::clone(self),
+        })
+    }
+
+    /// How many connections are being handled right now.
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.limiter.active.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+    use std::thread;
+
+    #[test]
+    fn rejects_past_the_limit() {
+        let limiter = Arc::new(ConnectionLimiter::new(2));
+
+        let first = limiter.try_acquire().unwrap();
+        let _second = limiter.try_acquire().unwrap();
+        assert!(limiter.try_acquire().is_none());
+        assert_eq!(2, limiter.active());
+
+        // a finished connection frees its slot
+        drop(first);
+        assert_eq!(1, limiter.active());
+        assert!(limiter.try_acquire().is_some());
+    }
+
+    #[test]
+    fn concurrent_connections_never_exceed_the_limit() {
+        let limiter = Arc::new(ConnectionLimiter::new(3));
+        let barrier = Arc::new(Barrier::new(10));
+
+        let handles: Vec<_> = (0..10)
+            .map(|_| {
+                let limiter = Arc::clone(&limiter);
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    let guard = limiter.try_acquire();
+                    // hold the slot until every thread has tried
+                    barrier.wait();
+                    guard.is_some()
+                })
+            })
+            .collect();
+
+        let accepted = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .filter(|&accepted| accepted)
+            .count();
+        assert_eq!(3, accepted);
+        assert_eq!(0, limiter.active());
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_connections_panics() {
+        ConnectionLimiter::new(0);
+    }
+}
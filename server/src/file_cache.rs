@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::fs;
+use std::hash::Hash;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// A map holding at most `capacity` entries, dropping the least recently
+/// used one to make room for a new one.
+///
+/// Finding the oldest entry looks at all of them, which is fine for the
+/// few dozen files a cache holds.
+pub struct LruCache<K, V> {
+    capacity: usize,
+    // every entry remembers when it was last used
+    entries: HashMap<K, (V, u64)>,
+    clock: u64,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    /// An empty cache. With a `capacity` of zero nothing is ever kept.
+    pub fn new(capacity: usize) -> LruCache<K, V> {
+        LruCache {
+            capacity,
+            entries: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// The value for `key`, which now counts as the most recently used.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.clock += 1;
+        let (value, used) = self.entries.get_mut(key)?;
+        *used = self.clock;
+        Some(value)
+    }
+
+    /// Add or replace the value for `key`, dropping the least recently used
+    /// entry when the cache is full.
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.clock += 1;
+        self.entries.insert(key, (value, self.clock));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// when the file was last modified, and what it held then
+type Entry = (SystemTime, Arc<Vec<u8>>);
+
+type Reader = dyn Fn(&Path) -> io::Result<Vec<u8>> + Send + Sync;
+
+/// The contents of recently served files, so that popular files aren't
+/// read from disk for every request.
+///
+/// A file is read again once its modification time changes. Clones share
+/// the same entries, so one cache can be handed to every worker.
+#[derive(Clone)]
+pub struct FileCache {
+    entries: Arc<Mutex<LruCache<PathBuf, Entry>>>,
+    read: Arc<Reader>,
+}
+
+impl FileCache {
+    /// A cache keeping the contents of up to `capacity` files.
+    pub fn new(capacity: usize) -> FileCache {
+        FileCache::with_reader(capacity, |path| fs::read(path))
+    }
+
+    /// Like [`FileCache::new`], but files are read with `read` instead of
+    /// `fs::read`.
+    pub fn with_reader<F>(capacity: usize, read: F) -> FileCache
+    where
+        F: Fn(&Path) -> io::Result<Vec<u8>> + Send + Sync + 'static,
+    {
+        FileCache {
+            entries: Arc::new(Mutex::new(LruCache::new(capacity))),
+            read: Arc::new(read),
+        }
+    }
+
+    /// The contents of the file at `path`, from the cache unless the file
+    /// changed since it was cached.
+    pub fn read(&self, path: &Path) -> io::Result<Arc<Vec<u8>>> {
+        let modified = fs::metadata(path)?.modified()?;
+        let key = path.to_path_buf();
+        if let Some((cached, contents)) = self.entries.lock().unwrap().get(&key) {
+            if *cached == modified {
+                return Ok(Arc::clone(contents));
+            }
+        }
+
+        // read without the lock, so other files can be served meanwhile
+        let contents = Arc::new((self.read)(path)?);
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key, (modified, Arc::clone(&contents)));
+        Ok(contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn least_recently_used_goes_first() {
+        let mut cache = LruCache::new(2);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        assert_eq!(Some(&1), cache.get(&"a"));
+
+        cache.insert("c", 3);
+        assert_eq!(2, cache.len());
+        assert_eq!(None, cache.get(&"b"));
+        assert_eq!(Some(&1), cache.get(&"a"));
+        assert_eq!(Some(&3), cache.get(&"c"));
+
+        // replacing a value makes no room
+        cache.insert("c", 4);
+        assert_eq!(Some(&1), cache.get(&"a"));
+        assert_eq!(Some(&4), cache.get(&"c"));
+    }
+
+    #[test]
+    fn zero_capacity_keeps_nothing() {
+        let mut cache = LruCache::new(0);
+        cache.insert("a", 1);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn changed_files_are_read_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("page.html");
+        fs::write(&path, "<p>one</p>").unwrap();
+
+        let reads = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&reads);
+        let cache = FileCache::with_reader(4, move |path| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            fs::read(path)
+        });
+        assert_eq!(b"<p>one</p>".to_vec(), *cache.read(&path).unwrap());
+        assert_eq!(b"<p>one</p>".to_vec(), *cache.read(&path).unwrap());
+        assert_eq!(1, reads.load(Ordering::SeqCst));
+
+        fs::write(&path, "<p>two</p>").unwrap();
+        // a write can land in the same tick of a coarse clock
+        let later = fs::metadata(&path).unwrap().modified().unwrap() + Duration::from_secs(1);
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert_eq!(b"<p>two</p>".to_vec(), *cache.read(&path).unwrap());
+        assert_eq!(2, reads.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn missing_files_are_errors() {
+        let cache = FileCache::new(4);
+        let e = cache.read(Path::new("missing.html")).unwrap_err();
+        assert_eq!(io::ErrorKind::NotFound, e.kind());
+    }
+}
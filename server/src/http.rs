@@ -0,0 +1,328 @@
+use crate::file_cache::FileCache;
+use crate::request;
+use crate::response::Response;
+use std::fs::{self, Metadata};
+use std::io::{self, prelude::*};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Read one request from `stream`, which comes from `peer`, and write the
+/// response back.
+///
+/// Every request is logged to stdout with the client's address, see
+/// [`request::client_ip`]. The whole response is written even when the
+/// stream only takes a few bytes at a time; an error reading or writing is
+/// returned. Files are read through `cache`.
+pub fn handle_connection<S: Read + Write>(
+    mut stream: S,
+    peer: SocketAddr,
+    cache: &FileCache,
+) -> io::Result<()> {
+    let mut buffer = [0; 1024];
+    let bytes_read = stream.read(&mut buffer)?;
+    let buffer = &buffer[..bytes_read];
+
+    // not even a request line, so there is nothing to route
+    let Some(request) = request::parse_request(buffer) else {
+        println!("{} - 400", peer.ip());
+        return Response::new(400).write_to(&mut stream);
+    };
+
+    let (status, filename) = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/") => (200, "hello.html"),
+        ("GET", "/sleep") => {
+            thread::sleep(Duration::from_secs(5));
+            (200, "hello.html")
+        }
+        _ => (404, "404.html"),
+    };
+    let client = request::client_ip(&request, peer);
+    println!("{} {} {} {}", client, request.method, request.path, status);
+
+    // the client already has this version of the file cached
+    let etag = compute_etag(&fs::metadata(filename)?);
+    let if_none_match = request.header("If-None-Match");
+    if status == 200 && if_none_match == Some(etag.as_str()) {
+        return Response::new(304)
+            .header("ETag", &etag)
+            .write_to(&mut stream);
+    }
+
+    write_file(&mut stream, status, Path::new(filename), &etag, cache)
+}
+
+/// Write a response with the file at `path` as its body.
+///
+/// The file is sent as raw bytes, so images and fonts arrive intact, and
+/// `Content-Length` is its length in bytes. The file is only read from disk
+/// when `cache` doesn't have it yet.
+pub fn write_file<W: Write>(
+    w: &mut W,
+    status: u16,
+    path: &Path,
+    etag: &str,
+    cache: &FileCache,
+) -> io::Result<()> {
+    Response::new(status)
+        .header("ETag", etag)
+        .body(cache.read(path)?.as_slice())
+        .write_to(w)
+}
+
+/// Write a body with `Transfer-Encoding: chunked`, for when its length
+/// isn't known upfront.
+///
+/// Every item from `body_source` is sent as one `<hex length>\r\n<data>\r\n`
+/// chunk as soon as it is produced, followed by the terminating
+/// `0\r\n\r\n`. Empty items are skipped, since a zero-length chunk would
+/// end the body early. The caller writes the status line and headers,
+/// including `Transfer-Encoding: chunked`, before calling this.
+pub fn write_chunked<W: Write>(
+    w: &mut W,
+    body_source: impl Iterator<Item = Vec<u8>>,
+) -> io::Result<()> {
+    for chunk in body_source.filter(|chunk| !chunk.is_empty()) {
+        write!(w, "{:x}\r\n", chunk.len())?;
+        w.write_all(&chunk)?;
+        w.write_all(b"\r\n")?;
+    }
+    w.write_all(b"0\r\n\r\n")?;
+    w.flush()
+}
+
+/// Compute an ETag from a file's size and modification time.
+///
+/// This is much cheaper than hashing the contents, and it changes whenever
+/// the file is written. The value is quoted, as HTTP requires.
+pub fn compute_etag(meta: &Metadata) -> String {
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .unwrap_or_default();
+
+    format!(
+        "\"{:x}-{:x}-{:x}\"",
+        meta.len(),
+        modified.as_secs(),
+        modified.subsec_nanos()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{self, Cursor};
+    use std::net::{Ipv4Addr, SocketAddrV4};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const PEER: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 40000));
+
+    /// A stream that reads a canned request and records the response.
+    struct MockStream {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+        // how many bytes one write takes at most, like a full socket buffer
+        max_write: usize,
+    }
+
+    impl MockStream {
+        fn new(request: &str) -> MockStream {
+            MockStream {
+                input: Cursor::new(request.as_bytes().to_vec()),
+                output: Vec::new(),
+                max_write: usize::MAX,
+            }
+        }
+
+        fn trickle(request: &str, max_write: usize) -> MockStream {
+            MockStream {
+                max_write,
+                ..MockStream::new(request)
+            }
+        }
+
+        fn response(&self) -> String {
+            String::from_utf8_lossy(&self.output).into_owned()
+        }
+    }
+
+    impl Read for MockStream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for MockStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.max_write);
+            self.output.write(&buf[..n])
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn etag_changes_with_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("page.html");
+        fs::write(&path, "<p>one</p>").unwrap();
+        let first = compute_etag(&fs::metadata(&path).unwrap());
+        assert_eq!(first, compute_etag(&fs::metadata(&path).unwrap()));
+        assert!(first.starts_with('"') && first.ends_with('"'));
+
+        fs::write(&path, "<p>two, longer</p>").unwrap();
+        assert_ne!(first, compute_etag(&fs::metadata(&path).unwrap()));
+    }
+
+    #[test]
+    fn conditional_request_gets_304() {
+        let mut stream = MockStream::new("GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        handle_connection(&mut stream, PEER, &FileCache::new(4)).unwrap();
+        let response = stream.response();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        let etag = response
+            .lines()
+            .find_map(|line| line.strip_prefix("ETag: "))
+            .unwrap()
+            .to_string();
+
+        let mut stream = MockStream::new(&format!(
+            "GET / HTTP/1.1\r\nHost: localhost\r\nIf-None-Match: {}\r\n\r\n",
+            etag
+        ));
+        handle_connection(&mut stream, PEER, &FileCache::new(4)).unwrap();
+        assert_eq!(
+            format!("HTTP/1.1 304 Not Modified\r\nETag: {}\r\n\r\n", etag),
+            stream.response()
+        );
+    }
+
+    #[test]
+    fn stale_etag_gets_the_full_file() {
+        let mut stream = MockStream::new("GET / HTTP/1.1\r\nif-none-match: \"0-0-0\"\r\n\r\n");
+        handle_connection(&mut stream, PEER, &FileCache::new(4)).unwrap();
+        let response = stream.response();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(&fs::read_to_string("hello.html").unwrap()));
+    }
+
+    #[test]
+    fn malformed_request_gets_400() {
+        for garbage in ["garbage\r\n\r\n", "GET /\r\n\r\n", ""] {
+            let mut stream = MockStream::new(garbage);
+            handle_connection(&mut stream, PEER, &FileCache::new(4)).unwrap();
+            assert_eq!(
+                "HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n",
+                stream.response()
+            );
+        }
+
+        let mut stream = MockStream::new("GET /missing HTTP/1.1\r\n\r\n");
+        handle_connection(&mut stream, PEER, &FileCache::new(4)).unwrap();
+        assert!(stream.response().starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+
+    #[test]
+    fn slow_streams_get_the_whole_response() {
+        let request = "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        let mut stream = MockStream::new(request);
+        handle_connection(&mut stream, PEER, &FileCache::new(4)).unwrap();
+
+        let mut trickle = MockStream::trickle(request, 3);
+        handle_connection(&mut trickle, PEER, &FileCache::new(4)).unwrap();
+        assert_eq!(stream.output, trickle.output);
+        assert!(trickle
+            .response()
+            .ends_with(&fs::read_to_string("hello.html").unwrap()));
+
+        // a stream that takes nothing at all is an error, not a lost response
+        let mut stuck = MockStream::trickle(request, 0);
+        let e = handle_connection(&mut stuck, PEER, &FileCache::new(4)).unwrap_err();
+        assert_eq!(io::ErrorKind::WriteZero, e.kind());
+    }
+
+    #[test]
+    fn second_request_is_served_from_the_cache() {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&reads);
+        let cache = FileCache::with_reader(4, move |path| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            fs::read(path)
+        });
+
+        let request = "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        let mut first = MockStream::new(request);
+        handle_connection(&mut first, PEER, &cache).unwrap();
+        let mut second = MockStream::new(request);
+        handle_connection(&mut second, PEER, &cache).unwrap();
+
+        assert_eq!(1, reads.load(Ordering::SeqCst));
+        assert_eq!(first.output, second.output);
+        assert!(second
+            .response()
+            .ends_with(&fs::read_to_string("hello.html").unwrap()));
+    }
+
+    #[test]
+    fn binary_files_are_sent_intact() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("favicon.ico");
+        let bytes = [0x00, 0x00, 0x01, 0x00, 0xff, 0xfe, 0x89, b'P', b'N', b'G'];
+        fs::write(&path, bytes).unwrap();
+
+        let mut response = Vec::new();
+        write_file(&mut response, 200, &path, "\"etag\"", &FileCache::new(4)).unwrap();
+
+        let head = b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\nETag: \"etag\"\r\n\r\n";
+        assert_eq!(&head[..], &response[..head.len()]);
+        assert_eq!(&bytes[..], &response[head.len()..]);
+    }
+
+    // Decode a chunked body, checking the framing along the way.
+    fn reassemble(mut encoded: &[u8]) -> Vec<u8> {
+        let mut body = Vec::new();
+        loop {
+            let line_end = encoded.windows(2).position(|w| w == b"\r\n").unwrap();
+            let size = std::str::from_utf8(&encoded[..line_end]).unwrap();
+            let size = usize::from_str_radix(size, 16).unwrap();
+            encoded = &encoded[line_end + 2..];
+            if size == 0 {
+                assert_eq!(b"\r\n", encoded);
+                return body;
+            }
+            body.extend_from_slice(&encoded[..size]);
+            assert_eq!(b"\r\n", &encoded[size..size + 2]);
+            encoded = &encoded[size + 2..];
+        }
+    }
+
+    #[test]
+    fn chunks_reassemble_to_the_source() {
+        let chunks: Vec<Vec<u8>> = vec![
+            b"<html>".to_vec(),
+            Vec::new(),
+            vec![b'x'; 300],
+            "<p>¡Hola!</p></html>".as_bytes().to_vec(),
+        ];
+        let source = chunks.concat();
+
+        let mut encoded = Vec::new();
+        write_chunked(&mut encoded, chunks.into_iter()).unwrap();
+
+        assert!(encoded.starts_with(b"6\r\n<html>\r\n12c\r\n"));
+        assert_eq!(source, reassemble(&encoded));
+    }
+
+    #[test]
+    fn empty_body_is_only_the_terminator() {
+        let mut encoded = Vec::new();
+        write_chunked(&mut encoded, std::iter::empty()).unwrap();
+        assert_eq!(b"0\r\n\r\n".to_vec(), encoded);
+    }
+}
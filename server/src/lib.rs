@@ -1,7 +1,24 @@
+use socket2::{Domain, Protocol, Socket, Type};
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::thread;
+use std::time::Duration;
+
+pub mod connection_limit;
+pub mod file_cache;
+pub mod http;
+pub mod rate_limit;
+pub mod request;
+pub mod response;
+
+pub use connection_limit::ConnectionLimiter;
+pub use file_cache::FileCache;
+pub use rate_limit::RateLimiter;
+pub use response::Response;
 
 pub struct ThreadPool {
     workers: Vec<Worker>,
@@ -99,3 +116,352 @@ impl Worker {
         }
     }
 }
+
+/// How long `bind_with_retry` waits after the first failed attempt. The
+/// wait doubles after every attempt after that.
+const BIND_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Socket options for the listener and the connections it accepts, and how
+/// many files are kept in memory for them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerConfig {
+    /// How many connections the OS queues up before they are accepted;
+    /// more are refused. 128 by default.
+    pub backlog: i32,
+    /// Set `TCP_NODELAY` on every accepted connection, so small responses
+    /// go out right away instead of waiting to fill a packet (Nagle's
+    /// algorithm). On by default.
+    pub nodelay: bool,
+    /// How many files a [`FileCache`] keeps the contents of; 0 reads every
+    /// file from disk each time. 32 by default.
+    pub cache_size: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> ServerConfig {
+        ServerConfig {
+            backlog: 128,
+            nodelay: true,
+            cache_size: 32,
+        }
+    }
+}
+
+/// Bind a listener to `addr` with the default [`ServerConfig`], retrying
+/// while the address is in use. See [`bind_with_config`].
+pub fn bind_with_retry(addr: &str, attempts: u32) -> io::Result<TcpListener> {
+    bind_with_config(addr, attempts, &ServerConfig::default())
+}
+
+/// Bind a listener to `addr` with `config.backlog`, retrying while the
+/// address is in use.
+///
+/// `SO_REUSEADDR` is set before binding, so after a restart the port can be
+/// bound again right away, even while connections of the old process are
+/// still in `TIME_WAIT`. If the port is really taken, like by a server that
+/// is still running, binding is tried up to `attempts` times (at least
+/// once), waiting 100ms, 200ms, 400ms, ... in between.
+pub fn bind_with_config(
+    addr: &str,
+    attempts: u32,
+    config: &ServerConfig,
+) -> io::Result<TcpListener> {
+    let addr = addr
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address to bind to"))?;
+    let mut delay = BIND_RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        match bind_reusable(addr, config.backlog) {
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse && attempt < attempts => {
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn bind_reusable(addr: SocketAddr, backlog: i32) -> io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    socket.set_reuse_address(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(backlog)?;
+    Ok(socket.into())
+}
+
+/// How long the accept loop sleeps when no connection is waiting.
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Accept connections until `shutdown` is set.
+///
+/// The listener is switched to non-blocking mode, so instead of blocking in
+/// `accept` the loop polls for new connections and checks the flag in
+/// between. Every accepted stream is handed to `handle` in blocking mode,
+/// with the options from `config`.
+///
+/// A connection that fails while it is accepted or set up is logged to
+/// stderr and dropped, and so is running out of file descriptors for a
+/// moment; the loop goes on with the next one. Only an error that means
+/// the listener itself can't be used ends the loop.
+pub fn accept_loop<F>(
+    listener: &TcpListener,
+    config: &ServerConfig,
+    shutdown: &AtomicBool,
+    handle: F,
+) -> io::Result<()>
+where
+    F: FnMut(TcpStream),
+{
+    listener.set_nonblocking(true)?;
+    accept_from(listener, config, shutdown, handle)
+}
+
+// Where the accept loop takes its connections from, so that tests can hand
+// it fake ones.
+trait Accept {
+    type Stream: StreamOptions;
+
+    fn accept(&self) -> io::Result<Self::Stream>;
+}
+
+impl Accept for TcpListener {
+    type Stream = TcpStream;
+
+    fn accept(&self) -> io::Result<TcpStream> {
+        TcpListener::accept(self).map(|(stream, _)| stream)
+    }
+}
+
+fn accept_from<L, F>(
+    listener: &L,
+    config: &ServerConfig,
+    shutdown: &AtomicBool,
+    mut handle: F,
+) -> io::Result<()>
+where
+    L: Accept,
+    F: FnMut(L::Stream),
+{
+    while !shutdown.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok(stream) => match configure_stream(&stream, config) {
+                Ok(()) => handle(stream),
+                Err(e) => eprintln!("Failed to set up a connection: {}", e),
+            },
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL_INTERVAL),
+            // EINVAL: the socket isn't listening (any more)
+            Err(e) if e.kind() == io::ErrorKind::InvalidInput => return Err(e),
+            Err(e) => {
+                // like ECONNABORTED, or EMFILE until a connection is closed
+                eprintln!("Failed to accept a connection: {}", e);
+                thread::sleep(ACCEPT_POLL_INTERVAL);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// The options the accept loop sets on a connection, so that tests can check
+// them on a fake one.
+trait StreamOptions {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()>;
+    fn set_nodelay(&self, nodelay: bool) -> io::Result<()>;
+}
+
+impl StreamOptions for TcpStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        TcpStream::set_nonblocking(self, nonblocking)
+    }
+
+    fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        TcpStream::set_nodelay(self, nodelay)
+    }
+}
+
+fn configure_stream(stream: &impl StreamOptions, config: &ServerConfig) -> io::Result<()> {
+    // some platforms hand out streams that inherit non-blocking mode
+    stream.set_nonblocking(false)?;
+    if config.nodelay {
+        stream.set_nodelay(true)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+    use std::collections::VecDeque;
+    use std::time::Instant;
+
+    #[test]
+    fn accept_loop_exits_when_shutdown_is_set() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let flag = Arc::clone(&shutdown);
+        let handle =
+            thread::spawn(move || accept_loop(&listener, &ServerConfig::default(), &flag, |_| {}));
+
+        thread::sleep(Duration::from_millis(50));
+        let start = Instant::now();
+        shutdown.store(true, Ordering::SeqCst);
+        handle.join().unwrap().unwrap();
+
+        assert!(start.elapsed() < Duration::from_millis(500));
+    }
+
+    #[test]
+    fn port_in_time_wait_can_be_bound_again() {
+        let listener = bind_with_retry("127.0.0.1:0", 1).unwrap();
+        let address = listener.local_addr().unwrap();
+
+        // the side that closes first keeps the connection in TIME_WAIT
+        let client = TcpStream::connect(address).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        drop(stream);
+        drop(listener);
+        drop(client);
+
+        let listener = bind_with_retry(&address.to_string(), 1).unwrap();
+        assert_eq!(address, listener.local_addr().unwrap());
+    }
+
+    #[test]
+    fn bind_gives_up_while_the_port_is_taken() {
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = taken.local_addr().unwrap().to_string();
+
+        let start = Instant::now();
+        let err = bind_with_retry(&address, 3).unwrap_err();
+        assert_eq!(io::ErrorKind::AddrInUse, err.kind());
+        // waited 100ms, then 200ms
+        assert!(start.elapsed() >= Duration::from_millis(300));
+    }
+
+    #[test]
+    fn accept_loop_hands_over_connections() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let shutdown = AtomicBool::new(false);
+
+        let client = thread::spawn(move || TcpStream::connect(address).unwrap());
+        let mut accepted = 0;
+        accept_loop(&listener, &ServerConfig::default(), &shutdown, |stream| {
+            assert!(stream.nodelay().unwrap());
+            accepted += 1;
+            shutdown.store(true, Ordering::SeqCst);
+        })
+        .unwrap();
+
+        client.join().unwrap();
+        assert_eq!(1, accepted);
+    }
+
+    // remembers what was set on it
+    #[derive(Default)]
+    struct FakeStream {
+        nonblocking: Cell<Option<bool>>,
+        nodelay: Cell<Option<bool>>,
+        // set_nodelay fails, like on a connection the client already reset
+        broken: bool,
+    }
+
+    impl StreamOptions for FakeStream {
+        fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+            self.nonblocking.set(Some(nonblocking));
+            Ok(())
+        }
+
+        fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+            if self.broken {
+                return Err(io::ErrorKind::ConnectionReset.into());
+            }
+            self.nodelay.set(Some(nodelay));
+            Ok(())
+        }
+    }
+
+    // Hands out the queued connections and errors, then nothing.
+    struct FakeListener {
+        queue: RefCell<VecDeque<io::Result<FakeStream>>>,
+    }
+
+    impl Accept for FakeListener {
+        type Stream = FakeStream;
+
+        fn accept(&self) -> io::Result<FakeStream> {
+            self.queue
+                .borrow_mut()
+                .pop_front()
+                .unwrap_or_else(|| Err(io::ErrorKind::WouldBlock.into()))
+        }
+    }
+
+    #[test]
+    fn failed_connections_dont_stop_the_loop() {
+        let broken = FakeStream {
+            broken: true,
+            ..FakeStream::default()
+        };
+        let listener = FakeListener {
+            queue: RefCell::new(VecDeque::from([
+                Ok(broken),
+                Err(io::ErrorKind::ConnectionAborted.into()),
+                // EMFILE
+                Err(io::Error::from_raw_os_error(24)),
+                Ok(FakeStream::default()),
+            ])),
+        };
+        let shutdown = AtomicBool::new(false);
+        let mut handled = 0;
+        accept_from(&listener, &ServerConfig::default(), &shutdown, |stream| {
+            assert_eq!(Some(true), stream.nodelay.get());
+            handled += 1;
+            shutdown.store(true, Ordering::SeqCst);
+        })
+        .unwrap();
+        assert_eq!(1, handled);
+    }
+
+    #[test]
+    fn unusable_listener_stops_the_loop() {
+        let listener = FakeListener {
+            queue: RefCell::new(VecDeque::from([Err(io::ErrorKind::InvalidInput.into())])),
+        };
+        let shutdown = AtomicBool::new(false);
+        let e = accept_from(&listener, &ServerConfig::default(), &shutdown, |_| {}).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, e.kind());
+    }
+
+    #[test]
+    fn accepted_streams_get_nodelay() {
+        let stream = FakeStream::default();
+        configure_stream(&stream, &ServerConfig::default()).unwrap();
+        assert_eq!(Some(true), stream.nodelay.get());
+        assert_eq!(Some(false), stream.nonblocking.get());
+
+        let stream = FakeStream::default();
+        let config = ServerConfig {
+            nodelay: false,
+            ..ServerConfig::default()
+        };
+        configure_stream(&stream, &config).unwrap();
+        assert_eq!(None, stream.nodelay.get());
+    }
+
+    #[test]
+    fn listener_takes_the_configured_backlog() {
+        let config = ServerConfig {
+            backlog: 1,
+            ..ServerConfig::default()
+        };
+        let listener = bind_with_config("127.0.0.1:0", 1, &config).unwrap();
+        let address = listener.local_addr().unwrap();
+        assert!(TcpStream::connect(address).is_ok());
+    }
+}
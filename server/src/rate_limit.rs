@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Buckets that haven't been used for this long are full again, so they
+/// can be dropped.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+struct Bucket {
+    tokens: f64,
+    last_seen: Instant,
+}
+
+/// A token-bucket rate limiter keyed by client IP.
+///
+/// Every IP starts with `per_second` tokens and gets `per_second` new tokens
+/// each second, up to that same maximum. Each request takes a token; when
+/// none are left the request should be rejected with `429 Too Many Requests`.
+pub struct RateLimiter {
+    per_second: f64,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+    last_prune: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Create a new RateLimiter allowing `per_second` requests per second
+    /// from each IP.
+    ///
+    /// # Panics
+    ///
+    /// The `new` function will panic if `per_second` is zero.
+    pub fn new(per_second: u32) -> RateLimiter {
+        assert!(per_second > 0);
+
+        RateLimiter {
+            per_second: per_second as f64,
+            buckets: Mutex::new(HashMap::new()),
+            last_prune: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Take a token for `ip`, returning false if it has run out.
+    pub fn check(&self, ip: IpAddr) -> bool {
+        self.check_at(ip, Instant::now())
+    }
+
+    fn check_at(&self, ip: IpAddr, now: Instant) -> bool {
+        self.prune_at(now);
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(ip).or_insert(Bucket {
+            tokens: self.per_second,
+            last_seen: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.last_seen);
+        bucket.tokens =
+            (bucket.tokens + elapsed.as_secs_f64() * self.per_second).min(self.per_second);
+        bucket.last_seen = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Drop idle buckets, at most once every `PRUNE_INTERVAL`.
+    fn prune_at(&self, now: Instant) {
+        let mut last_prune = self.last_prune.lock().unwrap();
+        if now.saturating_duration_since(*last_prune) < PRUNE_INTERVAL {
+            return;
+        }
+        *last_prune = now;
+
+        self.buckets
+            .lock()
+            .unwrap()
+            .retain(|_, bucket| now.saturating_duration_since(bucket.last_seen) < PRUNE_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    const CLIENT: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 168, 0, 7));
+    const OTHER: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 168, 0, 8));
+
+    #[test]
+    fn kicks_in_after_the_threshold() {
+        let limiter = RateLimiter::new(5);
+        let now = Instant::now();
+
+        let allowed = (0..20).filter(|_| limiter.check_at(CLIENT, now)).count();
+
+        assert_eq!(5, allowed);
+        // other clients have their own bucket
+        assert!(limiter.check_at(OTHER, now));
+    }
+
+    #[test]
+    fn refills_over_time() {
+        let limiter = RateLimiter::new(5);
+        let now = Instant::now();
+        for _ in 0..5 {
+            assert!(limiter.check_at(CLIENT, now));
+        }
+        assert!(!limiter.check_at(CLIENT, now));
+
+        // a fifth of a second buys one more request
+        let later = now + Duration::from_millis(200);
+        assert!(limiter.check_at(CLIENT, later));
+        assert!(!limiter.check_at(CLIENT, later));
+
+        // never more than a full bucket
+        let much_later = now + Duration::from_secs(30);
+        let allowed = (0..20)
+            .filter(|_| limiter.check_at(CLIENT, much_later))
+            .count();
+        assert_eq!(5, allowed);
+    }
+
+    #[test]
+    fn prunes_idle_clients() {
+        let limiter = RateLimiter::new(5);
+        let start = *limiter.last_prune.lock().unwrap();
+        limiter.check_at(CLIENT, start);
+        limiter.check_at(OTHER, start + Duration::from_secs(50));
+        assert_eq!(2, limiter.buckets.lock().unwrap().len());
+
+        limiter.check_at(OTHER, start + PRUNE_INTERVAL + Duration::from_secs(1));
+        assert_eq!(1, limiter.buckets.lock().unwrap().len());
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+
+/// A parsed HTTP request head.
+#[derive(Debug, PartialEq)]
+pub struct Request {
+    pub method: String,
+    /// The path without the query string, e.g. `/search`.
+    pub path: String,
+    pub version: String,
+    pub headers: Vec<(String, String)>,
+    /// The decoded `?key=value` pairs from the request target.
+    pub query_params: HashMap<String, String>,
+    /// Whatever followed the blank line after the headers.
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Find the value of a header, ignoring the name's case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parse the request line and headers at the start of `buffer`.
+///
+/// Returns `None` when the request line doesn't have a method, a target and
+/// an `HTTP/` version. Header lines without a colon are skipped.
+pub fn parse_request(buffer: &[u8]) -> Option<Request> {
+    let (head, body) = match buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+        Some(end) => (&buffer[..end], &buffer[end + 4..]),
+        None => (buffer, &[][..]),
+    };
+    let text = String::from_utf8_lossy(head);
+    let mut lines = text.split("\r\n");
+
+    let mut request_line = lines.next()?.split(' ');
+    let method = request_line.next().filter(|m| !m.is_empty())?;
+    let target = request_line.next().filter(|t| !t.is_empty())?;
+    let version = request_line.next().filter(|v| v.starts_with("HTTP/"))?;
+    if request_line.next().is_some() {
+        return None;
+    }
+
+    let headers = lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    let (path, query_params) = parse_query(target);
+
+    Some(Request {
+        method: method.to_string(),
+        path,
+        version: version.to_string(),
+        headers,
+        query_params,
+        body: body.to_vec(),
+    })
+}
+
+/// Split a request target into its path and its decoded query parameters.
+///
+/// Keys and values are percent-decoded, and `+` is decoded as a space. A
+/// parameter without `=` gets an empty value. When a key is repeated, the
+/// last value wins.
+pub fn parse_query(path: &str) -> (String, HashMap<String, String>) {
+    let (path, query) = match path.split_once('?') {
+        Some((path, query)) => (path, query),
+        None => (path, ""),
+    };
+
+    (path.to_string(), parse_pairs(query))
+}
+
+/// Parse an `application/x-www-form-urlencoded` body into its fields.
+///
+/// Decoding works like [`parse_query`]; an empty body has no fields.
+pub fn parse_form(body: &[u8]) -> HashMap<String, String> {
+    parse_pairs(&String::from_utf8_lossy(body))
+}
+
+/// The address of the client that sent `request`, for logging.
+///
+/// Behind a proxy, `peer` is the proxy, and the client is the leftmost
+/// address in `X-Forwarded-For`. Without that header, or when that address
+/// doesn't parse, this is the peer's address. The header is easy to fake,
+/// so don't use this for anything but logging.
+pub fn client_ip(request: &Request, peer: SocketAddr) -> IpAddr {
+    request
+        .header("X-Forwarded-For")
+        .and_then(|forwarded| forwarded.split(',').next())
+        .and_then(|client| {
+            let client = client.trim();
+            // some proxies add the port
+            client
+                .parse::<IpAddr>()
+                .or_else(|_| client.parse::<SocketAddr>().map(|addr| addr.ip()))
+                .ok()
+        })
+        .unwrap_or(peer.ip())
+}
+
+/// Parse `key=value&key=value` pairs, as used by query strings and form
+/// bodies.
+fn parse_pairs(pairs: &str) -> HashMap<String, String> {
+    pairs
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => (percent_decode(key), percent_decode(value)),
+            None => (percent_decode(pair), String::new()),
+        })
+        .collect()
+}
+
+/// Decode `%XX` escapes and `+` as a space. Escapes that aren't two hex
+/// digits are kept as they are, and invalid UTF-8 is replaced with `�`.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match hex {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn query_with_several_params() {
+        assert_eq!(
+            (
+                String::from("/search"),
+                params(&[("q", "foo"), ("n", "10")])
+            ),
+            parse_query("/search?q=foo&n=10")
+        );
+    }
+
+    #[test]
+    fn path_without_query() {
+        assert_eq!((String::from("/"), HashMap::new()), parse_query("/"));
+        assert_eq!((String::from("/"), HashMap::new()), parse_query("/?"));
+    }
+
+    #[test]
+    fn query_with_encoded_spaces() {
+        let (_, query) = parse_query("/search?q=hello%20world&name=Jane+Doe&a%26b=%C3%B1");
+        assert_eq!(
+            params(&[("q", "hello world"), ("name", "Jane Doe"), ("a&b", "ñ")]),
+            query
+        );
+    }
+
+    #[test]
+    fn query_with_missing_equals_and_repeated_keys() {
+        let (_, query) = parse_query("/search?debug&q=first&q=last&&empty=");
+        assert_eq!(
+            params(&[("debug", ""), ("q", "last"), ("empty", "")]),
+            query
+        );
+    }
+
+    #[test]
+    fn malformed_escapes_are_kept() {
+        let (_, query) = parse_query("/?q=100%&r=%zz&s=%4");
+        assert_eq!(params(&[("q", "100%"), ("r", "%zz"), ("s", "%4")]), query);
+    }
+
+    #[test]
+    fn parses_a_request() {
+        let request =
+            parse_request(b"GET /search?q=foo&n=10 HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+
+        assert_eq!("GET", request.method);
+        assert_eq!("/search", request.path);
+        assert_eq!("HTTP/1.1", request.version);
+        assert_eq!(Some("localhost"), request.header("host"));
+        assert_eq!(params(&[("q", "foo"), ("n", "10")]), request.query_params);
+    }
+
+    #[test]
+    fn parses_a_posted_form() {
+        let request = parse_request(
+            b"POST /signup HTTP/1.1\r\n\
+              Content-Type: application/x-www-form-urlencoded\r\n\
+              Content-Length: 16\r\n\
+              \r\n\
+              name=Jane&age=30",
+        )
+        .unwrap();
+
+        assert_eq!(b"name=Jane&age=30", &request.body[..]);
+        assert_eq!(
+            params(&[("name", "Jane"), ("age", "30")]),
+            parse_form(&request.body)
+        );
+    }
+
+    #[test]
+    fn form_fields_are_decoded() {
+        assert_eq!(
+            params(&[("name", "Jane Doe"), ("note", "a&b=c")]),
+            parse_form(b"name=Jane+Doe&note=a%26b%3Dc")
+        );
+        assert!(parse_form(b"").is_empty());
+    }
+
+    #[test]
+    fn rejects_a_malformed_request_line() {
+        assert_eq!(None, parse_request(b"garbage\r\n\r\n"));
+        assert_eq!(None, parse_request(b"GET /\r\n\r\n"));
+        assert_eq!(None, parse_request(b"GET / FTP\r\n\r\n"));
+        assert_eq!(None, parse_request(b""));
+    }
+
+    fn forwarded(value: &str) -> Request {
+        let head = format!("GET / HTTP/1.1\r\nX-Forwarded-For: {}\r\n\r\n", value);
+        parse_request(head.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn client_ip_comes_from_the_forwarded_header() {
+        let peer: SocketAddr = "10.0.0.1:40000".parse().unwrap();
+        assert_eq!(
+            "203.0.113.7".parse::<IpAddr>().unwrap(),
+            client_ip(&forwarded("203.0.113.7"), peer)
+        );
+        assert_eq!(
+            "2001:db8::1".parse::<IpAddr>().unwrap(),
+            client_ip(&forwarded("[2001:db8::1]:8080"), peer)
+        );
+
+        let request = parse_request(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(peer.ip(), client_ip(&request, peer));
+    }
+
+    #[test]
+    fn client_ip_is_the_leftmost_forwarded_address() {
+        let peer: SocketAddr = "10.0.0.1:40000".parse().unwrap();
+        assert_eq!(
+            "203.0.113.7".parse::<IpAddr>().unwrap(),
+            client_ip(&forwarded("203.0.113.7, 198.51.100.2, 10.0.0.2"), peer)
+        );
+    }
+
+    #[test]
+    fn garbage_forwarded_header_falls_back_to_the_peer() {
+        let peer: SocketAddr = "10.0.0.1:40000".parse().unwrap();
+        for garbage in ["unknown", "", "300.1.2.3", ", 203.0.113.7"] {
+            assert_eq!(
+                peer.ip(),
+                client_ip(&forwarded(garbage), peer),
+                "{}",
+                garbage
+            );
+        }
+    }
+}
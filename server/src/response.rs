@@ -0,0 +1,145 @@
+use std::io::{self, Write};
+
+/// An HTTP response, written out with [`Response::write_to`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    /// Sent in order after the status line. `Content-Length` is added by
+    /// `write_to`, so it doesn't belong here.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// A response with `status`, no headers and an empty body.
+    pub fn new(status: u16) -> Response {
+        Response {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Add a header.
+    pub fn header(mut self, name: &str, value: &str) -> Response {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Set the body.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Response {
+        self.body = body.into();
+        self
+    }
+
+    /// Write the status line, the headers and the body to `w`.
+    ///
+    /// `Content-Length` comes first after the status line, except for
+    /// statuses that never have a body, like 304.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(
+            w,
+            "HTTP/1.1 {} {}\r\n",
+            self.status,
+            reason_phrase(self.status)
+        )?;
+        if has_body(self.status) {
+            write!(w, "Content-Length: {}\r\n", self.body.len())?;
+        }
+        for (name, value) in &self.headers {
+            write!(w, "{}: {}\r\n", name, value)?;
+        }
+        w.write_all(b"\r\n")?;
+        w.write_all(&self.body)?;
+        w.flush()
+    }
+}
+
+/// The standard reason phrase for `status`, e.g. `Not Found` for 404.
+///
+/// Unknown codes get an empty phrase, which HTTP allows.
+pub fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        100 => "Continue",
+        101 => "Switching Protocols",
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        204 => "No Content",
+        206 => "Partial Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        303 => "See Other",
+        304 => "Not Modified",
+        307 => "Temporary Redirect",
+        308 => "Permanent Redirect",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        411 => "Length Required",
+        413 => "Content Too Large",
+        414 => "URI Too Long",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        505 => "HTTP Version Not Supported",
+        _ => "",
+    }
+}
+
+// 1xx, 204 and 304 responses end right after the headers.
+fn has_body(status: u16) -> bool {
+    !matches!(status, 100..=199 | 204 | 304)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(response: &Response) -> Vec<u8> {
+        let mut out = Vec::new();
+        response.write_to(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn ok_response_gets_its_length() {
+        let response = Response::new(200)
+            .header("Content-Type", "text/html")
+            .body("<p>Hi!</p>");
+        assert_eq!(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\nContent-Type: text/html\r\n\r\n<p>Hi!</p>"
+                .to_vec(),
+            bytes(&response)
+        );
+    }
+
+    #[test]
+    fn not_found_response() {
+        assert_eq!(
+            b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_vec(),
+            bytes(&Response::new(404))
+        );
+    }
+
+    #[test]
+    fn not_modified_has_no_length() {
+        let response = Response::new(304).header("ETag", "\"1-2-3\"");
+        assert_eq!(
+            b"HTTP/1.1 304 Not Modified\r\nETag: \"1-2-3\"\r\n\r\n".to_vec(),
+            bytes(&response)
+        );
+    }
+
+    #[test]
+    fn unknown_status_has_an_empty_phrase() {
+        assert_eq!("", reason_phrase(599));
+        assert!(bytes(&Response::new(599)).starts_with(b"HTTP/1.1 599 \r\n"));
+    }
+}
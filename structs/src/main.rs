@@ -1,12 +1,134 @@
+use std::error::Error;
+use std::fmt;
+
 #[allow(dead_code)] // This will allow unused variables to exist in our Rust programs.
+#[derive(Debug)]
 struct User {
     active: bool,
     username: String,
     email: String,
     sign_in_count: u64,
 }
+// Errors that can happen while building a User.
+#[derive(Debug, PartialEq)]
+enum UserError {
+    EmptyUsername,
+    // The email doesn't contain an @
+    InvalidEmail(String),
+}
+
+impl fmt::Display for UserError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UserError::EmptyUsername => write!(f, "the username can't be empty"),
+            UserError::InvalidEmail(email) => write!(f, "'{}' is not a valid email", email),
+        }
+    }
+}
+
+impl Error for UserError {}
+// Builder pattern: set the fields one by one, then validate them all in build().
+// A new user is active and hasn't signed in yet.
+struct UserBuilder {
+    active: bool,
+    username: String,
+    email: String,
+    sign_in_count: u64,
+}
+
+impl UserBuilder {
+    fn new() -> UserBuilder {
+        UserBuilder {
+            active: true,
+            username: String::new(),
+            email: String::new(),
+            sign_in_count: 0,
+        }
+    }
+
+    fn username(mut self, username: &str) -> UserBuilder {
+        self.username = String::from(username);
+        self
+    }
+
+    fn email(mut self, email: &str) -> UserBuilder {
+        self.email = String::from(email);
+        self
+    }
+
+    fn active(mut self, active: bool) -> UserBuilder {
+        self.active = active;
+        self
+    }
+
+    fn sign_in_count(mut self, sign_in_count: u64) -> UserBuilder {
+        self.sign_in_count = sign_in_count;
+        self
+    }
+
+    fn build(self) -> Result<User, UserError> {
+        if self.username.is_empty() {
+            return Err(UserError::EmptyUsername);
+        }
+        if !self.email.contains('@') {
+            return Err(UserError::InvalidEmail(self.email));
+        }
+        Ok(User {
+            active: self.active,
+            username: self.username,
+            email: self.email,
+            sign_in_count: self.sign_in_count,
+        })
+    }
+}
 // Tuple structs
+#[derive(Debug, PartialEq)]
 struct Color(i32, i32, i32);
+// Errors that can happen while parsing a hex color.
+#[derive(Debug, PartialEq)]
+enum ColorError {
+    // The color didn't have exactly six hex digits
+    InvalidLength(usize),
+    // A character wasn't a hex digit
+    InvalidDigit(char),
+}
+
+impl fmt::Display for ColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColorError::InvalidLength(len) => write!(f, "expected 6 hex digits, got {}", len),
+            ColorError::InvalidDigit(c) => write!(f, "'{}' is not a hex digit", c),
+        }
+    }
+}
+
+impl Error for ColorError {}
+
+impl Color {
+    // Formats the color as #rrggbb. Components outside 0..=255 are clamped.
+    fn to_hex(&self) -> String {
+        let clamp = |c: i32| c.clamp(0, 255);
+        format!(
+            "#{:02x}{:02x}{:02x}",
+            clamp(self.0),
+            clamp(self.1),
+            clamp(self.2)
+        )
+    }
+    // Parses #rrggbb or rrggbb, so every component is always in 0..=255.
+    fn from_hex(s: &str) -> Result<Color, ColorError> {
+        let digits = s.strip_prefix('#').unwrap_or(s);
+        if let Some(c) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(ColorError::InvalidDigit(c));
+        }
+        if digits.len() != 6 {
+            return Err(ColorError::InvalidLength(digits.len()));
+        }
+        // all six characters are ASCII hex digits, so slicing and parsing can't fail
+        let component = |i: usize| i32::from_str_radix(&digits[i..i + 2], 16).unwrap();
+        Ok(Color(component(0), component(2), component(4)))
+    }
+}
 // Unit-like struct wihout any fields.
 struct AlwaysEqual;
 #[derive(Debug)] // This will allow us to print out the structs in a more readable format.
@@ -30,6 +152,69 @@ impl Rectangle {
         self.width > other.width && self.height > other.height
     }
 }
+// Converting between a Rectangle and a (width, height) tuple.
+impl Rectangle {
+    // Associated function, called as Rectangle::from_tuple((30, 50))
+    fn from_tuple((width, height): (u32, u32)) -> Rectangle {
+        Rectangle { width, height }
+    }
+    // Takes ownership of the rectangle and gives back its dimensions
+    fn into_tuple(self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+// Geometry methods
+impl Rectangle {
+    // Casting to f64 before squaring so large sides can't overflow a u32
+    fn diagonal(&self) -> f64 {
+        let width = self.width as f64;
+        let height = self.height as f64;
+        (width * width + height * height).sqrt()
+    }
+}
+// A rectangle with a position: (x, y) is its top-left corner.
+#[derive(Debug, PartialEq)]
+struct PositionedRect {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+impl PositionedRect {
+    // Rectangles that only touch along an edge or a corner share no area,
+    // so they don't count as intersecting.
+    fn intersects(&self, other: &PositionedRect) -> bool {
+        self.intersection(other).is_some()
+    }
+    // The overlapping area, if there is any. Edges are computed as i64 so
+    // x + width can't overflow.
+    fn intersection(&self, other: &PositionedRect) -> Option<PositionedRect> {
+        let left = self.x.max(other.x);
+        let top = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+
+        if right <= left as i64 || bottom <= top as i64 {
+            return None;
+        }
+
+        Some(PositionedRect {
+            x: left,
+            y: top,
+            width: (right - left as i64) as u32,
+            height: (bottom - top as i64) as u32,
+        })
+    }
+
+    fn right(&self) -> i64 {
+        self.x as i64 + self.width as i64
+    }
+
+    fn bottom(&self) -> i64 {
+        self.y as i64 + self.height as i64
+    }
+}
 
 fn main() {
     let mut user1 = User {
@@ -44,8 +229,24 @@ fn main() {
         email: String::from("another@example.com"),
         ..user1
     };
+    // Builder pattern
+    match UserBuilder::new()
+        .username("someusername123")
+        .email("someone@example.com")
+        .active(true)
+        .sign_in_count(1)
+        .build()
+    {
+        Ok(user) => println!("Built {:?}", user),
+        Err(e) => println!("Couldn't build the user: {}", e),
+    }
     // Tuple structs
-    let _black = Color(0, 0, 0);
+    let black = Color(0, 0, 0);
+    println!("black is {}", black.to_hex());
+    match Color::from_hex("#ff8000") {
+        Ok(orange) => println!("#ff8000 is {:?}", orange),
+        Err(e) => println!("Couldn't parse the color: {}", e),
+    }
     // Unit-like struct instance
     let _subject = AlwaysEqual;
     // Structs with methods
@@ -72,4 +273,217 @@ fn main() {
 
     println!("Can rect1 hold rect2? {}", rect1.can_hold(&rect2));
     println!("Can rect1 hold rect3? {}", rect1.can_hold(&rect3));
-}
\ No newline at end of file
+    // Tuple conversions
+    let rect4 = Rectangle::from_tuple((20, 10));
+    println!("rect4 as a tuple is {:?}", rect4.into_tuple());
+    println!("The diagonal of rect1 is {:.2}", rect1.diagonal());
+    // Positioned rectangles
+    let a = PositionedRect {
+        x: 0,
+        y: 0,
+        width: 30,
+        height: 50,
+    };
+    let b = PositionedRect {
+        x: 20,
+        y: 40,
+        width: 30,
+        height: 50,
+    };
+    println!("Does a intersect b? {}", a.intersects(&b));
+    println!("The overlap is {:?}", a.intersection(&b));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_tuple_sets_width_and_height() {
+        let rect = Rectangle::from_tuple((30, 50));
+        assert_eq!(30, rect.width);
+        assert_eq!(50, rect.height);
+    }
+
+    #[test]
+    fn tuple_round_trip() {
+        for dimensions in [(30, 50), (0, 0), (u32::MAX, 1)] {
+            assert_eq!(dimensions, Rectangle::from_tuple(dimensions).into_tuple());
+        }
+        let rect = Rectangle {
+            width: 10,
+            height: 40,
+        };
+        let rect = Rectangle::from_tuple(rect.into_tuple());
+        assert_eq!(400, rect.area());
+    }
+
+    #[test]
+    fn diagonal_of_a_3_4_5_rectangle() {
+        let rect = Rectangle {
+            width: 3,
+            height: 4,
+        };
+        assert_eq!(5.0, rect.diagonal());
+    }
+
+    #[test]
+    fn diagonal_of_a_zero_rectangle() {
+        let rect = Rectangle {
+            width: 0,
+            height: 0,
+        };
+        assert_eq!(0.0, rect.diagonal());
+    }
+
+    #[test]
+    fn diagonal_of_large_sides_does_not_overflow() {
+        let rect = Rectangle {
+            width: u32::MAX,
+            height: u32::MAX,
+        };
+        let expected = u32::MAX as f64 * 2.0_f64.sqrt();
+        assert!((rect.diagonal() - expected).abs() / expected < 1e-12);
+    }
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) -> PositionedRect {
+        PositionedRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn overlapping_rectangles_intersect() {
+        let a = rect(0, 0, 30, 50);
+        let b = rect(20, 40, 30, 50);
+        assert!(a.intersects(&b));
+        assert!(b.intersects(&a));
+        assert_eq!(Some(rect(20, 40, 10, 10)), a.intersection(&b));
+        assert_eq!(a.intersection(&b), b.intersection(&a));
+    }
+
+    #[test]
+    fn disjoint_rectangles_do_not_intersect() {
+        let a = rect(0, 0, 10, 10);
+        let b = rect(20, -30, 5, 5);
+        assert!(!a.intersects(&b));
+        assert_eq!(None, a.intersection(&b));
+    }
+
+    #[test]
+    fn touching_rectangles_do_not_intersect() {
+        let a = rect(0, 0, 10, 10);
+        // shares the right edge
+        assert!(!a.intersects(&rect(10, 0, 10, 10)));
+        // shares only the bottom-right corner
+        assert!(!a.intersects(&rect(10, 10, 10, 10)));
+    }
+
+    #[test]
+    fn contained_rectangle_is_the_intersection() {
+        let outer = rect(-10, -10, 100, 100);
+        let inner = rect(5, 5, 10, 20);
+        assert_eq!(Some(rect(5, 5, 10, 20)), outer.intersection(&inner));
+        assert_eq!(Some(rect(5, 5, 10, 20)), inner.intersection(&outer));
+    }
+
+    #[test]
+    fn far_edges_do_not_overflow() {
+        let a = rect(i32::MAX - 5, 0, u32::MAX, 10);
+        let b = rect(i32::MAX - 10, 0, 10, 10);
+        assert_eq!(Some(rect(i32::MAX - 5, 0, 5, 10)), a.intersection(&b));
+    }
+
+    #[test]
+    fn color_hex_round_trip() {
+        for color in [
+            Color(0, 0, 0),
+            Color(255, 255, 255),
+            Color(255, 128, 0),
+            Color(18, 52, 86),
+        ] {
+            assert_eq!(Ok(&color), Color::from_hex(&color.to_hex()).as_ref());
+        }
+        assert_eq!("#ff8000", Color(255, 128, 0).to_hex());
+    }
+
+    #[test]
+    fn color_from_hex_with_or_without_hash() {
+        assert_eq!(Ok(Color(171, 205, 239)), Color::from_hex("#abcdef"));
+        assert_eq!(Ok(Color(171, 205, 239)), Color::from_hex("ABCDEF"));
+    }
+
+    #[test]
+    fn color_to_hex_clamps_components() {
+        assert_eq!("#00ff80", Color(-20, 300, 128).to_hex());
+    }
+
+    #[test]
+    fn color_from_malformed_hex() {
+        assert_eq!(Err(ColorError::InvalidLength(5)), Color::from_hex("#fffff"));
+        assert_eq!(
+            Err(ColorError::InvalidLength(8)),
+            Color::from_hex("#ffffff00")
+        );
+        assert_eq!(Err(ColorError::InvalidLength(0)), Color::from_hex("#"));
+        assert_eq!(
+            Err(ColorError::InvalidDigit('g')),
+            Color::from_hex("#ggffff")
+        );
+        assert_eq!(
+            Err(ColorError::InvalidDigit('#')),
+            Color::from_hex("##ffffff")
+        );
+        assert_eq!(
+            Err(ColorError::InvalidDigit('é')),
+            Color::from_hex("éfffff")
+        );
+    }
+
+    #[test]
+    fn builder_with_defaults() {
+        let user = UserBuilder::new()
+            .username("someusername123")
+            .email("someone@example.com")
+            .build()
+            .unwrap();
+        assert_eq!("someusername123", user.username);
+        assert_eq!("someone@example.com", user.email);
+        assert!(user.active);
+        assert_eq!(0, user.sign_in_count);
+    }
+
+    #[test]
+    fn builder_with_every_field() {
+        let user = UserBuilder::new()
+            .username("someusername123")
+            .email("someone@example.com")
+            .active(false)
+            .sign_in_count(7)
+            .build()
+            .unwrap();
+        assert!(!user.active);
+        assert_eq!(7, user.sign_in_count);
+    }
+
+    #[test]
+    fn builder_rejects_an_empty_username() {
+        let result = UserBuilder::new().email("someone@example.com").build();
+        assert_eq!(UserError::EmptyUsername, result.unwrap_err());
+    }
+
+    #[test]
+    fn builder_rejects_an_email_without_at() {
+        let result = UserBuilder::new()
+            .username("someusername123")
+            .email("someone.example.com")
+            .build();
+        assert_eq!(
+            UserError::InvalidEmail(String::from("someone.example.com")),
+            result.unwrap_err()
+        );
+    }
+}
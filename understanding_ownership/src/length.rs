@@ -0,0 +1,45 @@
+/// Returns the length in bytes of anything that can be viewed as a `str`.
+///
+/// Pass a reference (`&s`) to keep ownership of a `String`: `&String`
+/// implements `AsRef<str>` too, so the caller can keep using it afterwards.
+pub fn length<T: AsRef<str>>(s: T) -> usize {
+    s.as_ref().len()
+}
+
+/// Returns the number of elements in a slice.
+///
+/// Works on arrays and vectors alike, since `&Vec<T>` derefs to `&[T]`.
+pub fn length_of<T>(items: &[T]) -> usize {
+    items.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn length_of_str_and_string() {
+        assert_eq!(5, length("hello"));
+        assert_eq!(0, length(""));
+        assert_eq!(11, length(String::from("hello world")));
+        // multibyte characters count bytes, just like String::len
+        assert_eq!(4, length("😻"));
+    }
+
+    #[test]
+    fn length_keeps_the_string_usable() {
+        let s1 = String::from("hello");
+        let len = length(&s1);
+        assert_eq!(5, len);
+        assert_eq!("hello", s1);
+    }
+
+    #[test]
+    fn length_of_slices() {
+        let v = vec![1, 2, 3];
+        assert_eq!(3, length_of(&v));
+        assert_eq!(v, [1, 2, 3]);
+        assert_eq!(2, length_of(&["a", "b"]));
+        assert_eq!(0, length_of::<String>(&[]));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod length;
+pub mod ownership;
+pub mod slices;
+pub mod stack;
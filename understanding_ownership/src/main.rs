@@ -1,4 +1,10 @@
 #![allow(dead_code)]
+// The examples below spell things out the way the book does
+#![allow(clippy::let_and_return, clippy::ptr_arg)]
+
+use understanding_ownership::length::length;
+use understanding_ownership::ownership::{take_and_return, with_borrow};
+use understanding_ownership::slices::{first_word, safe_slice};
 
 fn main() {
     {                      // s is not valid here, it’s not yet declared
@@ -33,6 +39,13 @@ fn main() {
     let s1 = String::from("hello");
     let len = calculating_length(&s1);
     println!("The length of '{}' is {}.", s1, len);
+    // The generic version works on both &str and &String
+    let len = length(&s1);
+    println!("The length of '{}' is still {}.", s1, len);
+    // The same ideas as reusable generic functions
+    let s1 = take_and_return(s1);
+    let len = with_borrow(&s1, |s| s.len());
+    println!("The length of '{}' is {} once more.", s1, len);
     // Mutable references
     let mut s = String::from("hello");
     change(&mut s);
@@ -40,6 +53,9 @@ fn main() {
     let s = String::from("hello world");
     let _hello = &s[0..5];
     let _world = &s[6..11];
+    // &s[0..5] panics if 5 isn't on a char boundary, safe_slice doesn't
+    println!("{:?}", safe_slice(&s, 0, 5));
+    println!("The first word is: {}", first_word(&s));
 }
 
 fn takes_ownership(some_string: String) { // some_string comes into scope
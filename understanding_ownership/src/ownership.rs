@@ -0,0 +1,44 @@
+/// Takes ownership of `value` and gives it straight back, like
+/// `takes_and_gives_back` in `main` but for any type.
+///
+/// The caller's variable is moved in, so it can't be used again until the
+/// return value is bound to a name.
+pub fn take_and_return<T>(value: T) -> T {
+    value
+}
+
+/// Lends `value` to `f` and returns whatever `f` computes from it.
+///
+/// Only a reference is passed, so the caller still owns `value` afterwards.
+pub fn with_borrow<T, R>(value: &T, f: impl Fn(&T) -> R) -> R {
+    f(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn take_and_return_gives_the_same_value_back() {
+        let s1 = String::from("hello");
+        let s2 = take_and_return(s1);
+        assert_eq!("hello", s2);
+        assert_eq!(vec![1, 2, 3], take_and_return(vec![1, 2, 3]));
+        assert_eq!(5, take_and_return(5));
+    }
+
+    #[test]
+    fn with_borrow_leaves_the_value_usable() {
+        let s = String::from("hello world");
+        let len = with_borrow(&s, |s| s.len());
+        assert_eq!(11, len);
+        // s was only borrowed, so it can still be moved
+        let moved = take_and_return(s);
+        assert_eq!("hello world", moved);
+
+        let v = vec![String::from("a"), String::from("b")];
+        let first = with_borrow(&v, |v| v[0].clone());
+        assert_eq!("a", first);
+        assert_eq!(2, v.len());
+    }
+}
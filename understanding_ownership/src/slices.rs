@@ -0,0 +1,67 @@
+/// Returns `&s[start..end]`, or `None` where slicing would panic: when the
+/// range is out of bounds, backwards, or cuts a character in half.
+pub fn safe_slice(s: &str, start: usize, end: usize) -> Option<&str> {
+    s.get(start..end)
+}
+
+/// Returns the first word of `s`, or all of it if there is no space.
+///
+/// The result borrows from `s`, so `s` can't be changed while the word is
+/// in use.
+pub fn first_word(s: &str) -> &str {
+    let bytes = s.as_bytes();
+
+    for (i, &item) in bytes.iter().enumerate() {
+        if item == b' ' {
+            return &s[0..i];
+        }
+    }
+
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_slice_within_bounds() {
+        let s = String::from("hello world");
+        assert_eq!(Some("hello"), safe_slice(&s, 0, 5));
+        assert_eq!(Some("world"), safe_slice(&s, 6, 11));
+        assert_eq!(Some(""), safe_slice(&s, 11, 11));
+    }
+
+    #[test]
+    fn safe_slice_out_of_range() {
+        assert_eq!(None, safe_slice("hello", 0, 6));
+        assert_eq!(None, safe_slice("hello", 6, 6));
+        assert_eq!(None, safe_slice("hello", 3, 2));
+    }
+
+    #[test]
+    fn safe_slice_multibyte() {
+        // "З" and "д" take two bytes each
+        let s = "Здравствуйте";
+        assert_eq!(Some("Зд"), safe_slice(s, 0, 4));
+        assert_eq!(None, safe_slice(s, 0, 1));
+        assert_eq!(None, safe_slice(s, 1, 4));
+
+        let s = "I 😻 Rust";
+        assert_eq!(Some("😻"), safe_slice(s, 2, 6));
+        assert_eq!(None, safe_slice(s, 2, 5));
+    }
+
+    #[test]
+    fn first_word_stops_at_the_first_space() {
+        assert_eq!("hello", first_word("hello world"));
+        assert_eq!("hello", first_word("hello"));
+        assert_eq!("", first_word(" hello"));
+        assert_eq!("", first_word(""));
+        assert_eq!("Здравствуйте", first_word("Здравствуйте мир"));
+
+        let s = String::from("hello world");
+        assert_eq!("hello", first_word(&s[..]));
+        assert_eq!("world", first_word(&s[6..]));
+    }
+}
@@ -0,0 +1,94 @@
+/// A last-in, first-out stack that owns its values.
+///
+/// Pushing moves a value into the stack and popping moves it back out to the
+/// caller, while `peek` only lends a reference to the top value.
+#[derive(Debug)]
+pub struct Stack<T> {
+    items: Vec<T>,
+}
+
+impl<T> Stack<T> {
+    pub fn new() -> Stack<T> {
+        Stack { items: Vec::new() }
+    }
+
+    pub fn push(&mut self, item: T) {
+        self.items.push(item);
+    }
+
+    /// Removes the top value and gives ownership of it back, or returns
+    /// `None` if the stack is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        self.items.pop()
+    }
+
+    /// Borrows the top value without removing it.
+    pub fn peek(&self) -> Option<&T> {
+        self.items.last()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl<T> Default for Stack<T> {
+    fn default() -> Stack<T> {
+        Stack::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pops_in_lifo_order() {
+        let mut stack = Stack::new();
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+        assert_eq!(3, stack.len());
+        assert_eq!(Some(3), stack.pop());
+        assert_eq!(Some(2), stack.pop());
+        stack.push(4);
+        assert_eq!(Some(4), stack.pop());
+        assert_eq!(Some(1), stack.pop());
+    }
+
+    #[test]
+    fn peek_borrows_the_top() {
+        let mut stack = Stack::new();
+        assert_eq!(None, stack.peek());
+
+        stack.push(String::from("hello"));
+        stack.push(String::from("world"));
+        assert_eq!(Some(&String::from("world")), stack.peek());
+        // peeking doesn't take the value out
+        assert_eq!(2, stack.len());
+    }
+
+    #[test]
+    fn draining_leaves_an_empty_stack() {
+        let mut stack: Stack<String> = Stack::default();
+        assert!(stack.is_empty());
+        for word in ["Do", "g", "Cat"] {
+            stack.push(String::from(word));
+        }
+
+        let mut drained = Vec::new();
+        while let Some(word) = stack.pop() {
+            drained.push(word);
+        }
+
+        assert_eq!(vec!["Cat", "g", "Do"], drained);
+        assert!(stack.is_empty());
+        assert_eq!(0, stack.len());
+        assert_eq!(None, stack.pop());
+        assert_eq!(None, stack.peek());
+    }
+}